@@ -2,7 +2,8 @@ use crate::common::*;
 use crate::interval::Interval;
 use crate::ray::Ray;
 
-#[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy)]
 pub(crate) struct AABB {
     ix: Interval,
     iy: Interval,
@@ -22,6 +23,20 @@ impl AABB {
         Self::new(ix, iy, iz)
     }
 
+    /// Pad flat axes so the box keeps a non-zero thickness, e.g. for axis-aligned triangles.
+    pub fn pad_to_minimums(self) -> Self {
+        let delta = 0.0001;
+        let pad = |interval: Interval| {
+            if interval.size() < delta { interval.expand(delta) } else { interval }
+        };
+        Self::new(pad(self.ix), pad(self.iy), pad(self.iz))
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let (x, y, z) = (self.ix.size(), self.iy.size(), self.iz.size());
+        2.0 * (x * y + y * z + z * x)
+    }
+
     pub fn merge(a: &AABB, b: &AABB) -> Self {
         Self::new(
             Interval::merge(&a.ix, &b.ix),
@@ -45,34 +60,19 @@ impl AABB {
         }
     }
 
+    /// Slab test without branches. A ray parallel to a slab gets infinite distances from
+    /// its `inv_direction`, which rule the box in or out on their own; the NaN of a ray
+    /// lying exactly in a slab plane is dropped by `max`/`min`, counting it as inside.
     pub fn hit(&self, ray: &Ray, ray_t: &Interval) -> bool {
-        let mut time_interval = *ray_t; // Interval is Copy
-        for axis in 0..3 {
-            let ax = self.axis_interval(axis);
-            let dir = ray.direction[axis];
-            // Handle near-zero direction to avoid division by zero; if ray is parallel, just check origin within slab
-            if dir.abs() < 1e-12 {
-                if ray.origin[axis] < ax.start || ray.origin[axis] > ax.end {
-                    return false;
-                }
-                continue;
-            }
-
-            let inv_d = 1.0 / dir;
-            let mut t0 = (ax.start - ray.origin[axis]) * inv_d;
-            let mut t1 = (ax.end - ray.origin[axis]) * inv_d;
-
-            if t0 > t1 {
-                std::mem::swap(&mut t0, &mut t1);
-            }
-
-            if t0 > time_interval.start { time_interval.start = t0; }
-            if t1 < time_interval.end { time_interval.end = t1; }
-
-            if time_interval.start > time_interval.end {
-                return false;
-            }
+        let mut t_min = ray_t.start;
+        let mut t_max = ray_t.end;
+        for (axis, interval) in [self.ix, self.iy, self.iz].iter().enumerate() {
+            let bounds = [interval.start, interval.end];
+            let near = (bounds[ray.sign[axis]] - ray.origin[axis]) * ray.inv_direction[axis];
+            let far = (bounds[1 - ray.sign[axis]] - ray.origin[axis]) * ray.inv_direction[axis];
+            t_min = near.max(t_min);
+            t_max = far.min(t_max);
         }
-        true
+        t_min <= t_max
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,99 @@
+use crate::common::*;
+use std::fmt;
+use std::path::Path;
+
+/// Radiance arriving from infinitely far away along rays that miss every object.
+pub trait Background: Send + Sync + fmt::Debug {
+    fn value(&self, direction: &Vec3) -> Color;
+}
+
+#[derive(Debug)]
+pub struct SolidBackground {
+    pub color: Color,
+}
+
+impl SolidBackground {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Background for SolidBackground {
+    fn value(&self, _direction: &Vec3) -> Color {
+        self.color
+    }
+}
+
+/// Vertical blend from `bottom` (looking straight down) to `top` (straight up).
+#[derive(Debug)]
+pub struct GradientBackground {
+    pub bottom: Color,
+    pub top: Color,
+}
+
+impl GradientBackground {
+    pub fn new(bottom: Color, top: Color) -> Self {
+        Self { bottom, top }
+    }
+
+    /// The white to light blue sky of the book.
+    pub fn sky() -> Self {
+        Self::new(Color::new(1.0, 1.0, 1.0), Color::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Background for GradientBackground {
+    fn value(&self, direction: &Vec3) -> Color {
+        let unit_direction = direction.normalize();
+        let t = 0.5 * (unit_direction.y + 1.0);
+        (1.0 - t) * self.bottom + t * self.top
+    }
+}
+
+/// Equirectangular (latitude-longitude) environment map, usually an HDR image.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    intensity: f64,
+}
+
+impl EnvironmentMap {
+    pub fn load<P: AsRef<Path>>(path: P, intensity: f64) -> image::ImageResult<Self> {
+        let image = image::open(path)?.into_rgb32f();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let pixels = image
+            .pixels()
+            .map(|p| Color::new(p[0] as f64, p[1] as f64, p[2] as f64))
+            .collect();
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            intensity,
+        })
+    }
+}
+
+impl fmt::Debug for EnvironmentMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnvironmentMap")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("intensity", &self.intensity)
+            .finish()
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn value(&self, direction: &Vec3) -> Color {
+        let d = direction.normalize();
+        // u: longitude around the y axis, v: latitude from the top row (+y) down
+        let u = ((-d.z).atan2(d.x) + PI) / (2.0 * PI);
+        let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.intensity * self.pixels[j * self.width + i]
+    }
+}
@@ -0,0 +1,107 @@
+use crate::bvh::{BvhNode, LinearBvh, WideBvh};
+use crate::camera::Camera;
+use crate::common::*;
+use crate::hittable::{Hittable, Interval};
+use crate::ray::Ray;
+use crate::tlas::Tlas;
+use nalgebra::Matrix4;
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use std::time::{Duration, Instant};
+
+/// Passes over the ray set per traversal; the fastest one is reported.
+const PASSES: usize = 5;
+
+/// Time closest hit queries on the tree `BvhNode` and on the `LinearBvh` and `WideBvh`
+/// flattened from it, single threaded. The rays are one camera ray per pixel plus a
+/// diffuse bounce from each camera ray that hits something, so both coherent and
+/// incoherent rays are covered.
+pub fn bench_bvh(camera: &Camera, tree: &BvhNode, seed: u64) {
+    let rays = sample_rays(camera, tree, seed);
+    let linear = LinearBvh::new(tree);
+    let wide = WideBvh::new(tree);
+    let layouts: [(&str, &dyn Hittable); 3] = [("tree", tree), ("linear", &linear), ("wide", &wide)];
+
+    println!("BVH benchmark: {} rays, best of {} passes", rays.len(), PASSES);
+    let interval = Interval::new(0.001, f64::INFINITY);
+    let mut tree_time = None;
+    for (name, bvh) in layouts {
+        let time = time_traversal(&rays, bvh);
+        let tree_time = *tree_time.get_or_insert(time);
+        println!(
+            "  {:7} {:8.2} ms  {:6.2} Mrays/s  ({:.2}x)",
+            format!("{}:", name),
+            time.as_secs_f64() * 1e3,
+            rays.len() as f64 / time.as_secs_f64() / 1e6,
+            tree_time.as_secs_f64() / time.as_secs_f64()
+        );
+
+        // Every layout has to agree with the tree on the closest hits
+        let mismatches = rays
+            .iter()
+            .filter(|ray| {
+                let expected = tree.hit(ray, &interval).map(|record| record.t);
+                expected != bvh.hit(ray, &interval).map(|record| record.t)
+            })
+            .count();
+        if mismatches > 0 {
+            println!("  warning: {} rays hit differently", mismatches);
+        }
+    }
+}
+
+/// Time moving every instance of `tlas` a little and rebuilding only its top level, as
+/// between animation frames. The instances are put back afterwards.
+pub fn bench_tlas(tlas: &mut Tlas) {
+    let original: Vec<Matrix4<f64>> = tlas.instances().iter().map(|instance| *instance.transform()).collect();
+    let nudge = Matrix4::new_translation(&Vec3::new(0.0, 1e-3, 0.0));
+    let moved: Vec<Matrix4<f64>> = original.iter().map(|transform| nudge * transform).collect();
+
+    let time = (0..PASSES)
+        .map(|_| {
+            let start = Instant::now();
+            tlas.update_instances(&moved);
+            start.elapsed()
+        })
+        .min()
+        .unwrap();
+    tlas.update_instances(&original);
+    println!(
+        "TLAS rebuild: {} instances in {:.3} ms, best of {} passes",
+        original.len(),
+        time.as_secs_f64() * 1e3,
+        PASSES
+    );
+}
+
+fn sample_rays(camera: &Camera, world: &impl Hittable, seed: u64) -> Vec<Ray> {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let (width, height) = camera.resolution();
+    let mut rays = Vec::with_capacity(2 * width * height);
+    for j in 0..height {
+        for i in 0..width {
+            let ray = camera.get_ray(i, j, &mut rng);
+            if let Some(record) = world.hit(&ray, &Interval::new(0.001, f64::INFINITY)) {
+                let direction = record.normal + random_unit_vec3(&mut rng);
+                if !near_zero(&direction) {
+                    rays.push(Ray::new(record.point, direction, ray.time));
+                }
+            }
+            rays.push(ray);
+        }
+    }
+    rays
+}
+
+fn time_traversal(rays: &[Ray], bvh: &(impl Hittable + ?Sized)) -> Duration {
+    let interval = Interval::new(0.001, f64::INFINITY);
+    (0..PASSES)
+        .map(|_| {
+            let start = Instant::now();
+            let hits = rays.iter().filter(|ray| bvh.hit(ray, &interval).is_some()).count();
+            std::hint::black_box(hits);
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
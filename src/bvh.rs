@@ -1,90 +1,656 @@
 use crate::aabb::AABB;
+use crate::common::*;
 use crate::hittable::{HitRecord, Hittable, Interval};
 use crate::interval::Interval as AxisInterval;
 use crate::ray::Ray;
+use rayon::prelude::*;
+use std::fmt;
 use std::sync::Arc;
+use wide::{f64x4, CmpLe};
 
+/// Deepest tree the linear traversal stack can hold; builds switch to median splits,
+/// which halve the object count, before they could go deeper.
+const MAX_DEPTH: usize = 64;
+/// Subtrees with at least this many objects are built on the rayon thread pool.
+const PARALLEL_THRESHOLD: usize = 1024;
+/// Number of buckets the centroids are sorted into when evaluating SAH splits.
+const BIN_COUNT: usize = 16;
+// Relative costs of visiting a node and intersecting a primitive, for the SAH
+const TRAVERSAL_COST: f64 = 1.0;
+const INTERSECTION_COST: f64 = 1.0;
+/// Largest allowed `BvhConfig::max_leaf_size`. Flattened nodes store the primitive count
+/// of a leaf in a `u16`, which packs with the axis into the padding after the offset
+/// and keeps `LinearNode` at 56 bytes; builds never make a leaf larger than the setting.
+pub const MAX_LEAF_SIZE: usize = u16::MAX as usize;
+
+/// How `BvhNode::from_list` divides the objects of a node between its two children.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SplitMethod {
+    /// Half the objects on each side, along the longest axis.
+    #[default]
+    Median,
+    /// The binned split with the lowest surface area heuristic cost.
+    Sah,
+}
+
+impl SplitMethod {
+    pub fn from_name(name: &str) -> Option<SplitMethod> {
+        match name.to_ascii_lowercase().as_str() {
+            "median" => Some(SplitMethod::Median),
+            "sah" => Some(SplitMethod::Sah),
+            _ => None,
+        }
+    }
+}
+
+/// Settings for building a BVH.
+#[derive(Debug, Clone, Copy)]
+pub struct BvhConfig {
+    pub split: SplitMethod,
+    /// Most objects a leaf may hold. SAH builds only fill leaves up to it when splitting
+    /// would not be cheaper; median builds stop splitting at this size. At most
+    /// `MAX_LEAF_SIZE`.
+    pub max_leaf_size: usize,
+    /// Collapse the tree into 4-wide nodes tested with SIMD, see `WideBvh`.
+    pub wide: bool,
+}
+
+impl Default for BvhConfig {
+    fn default() -> Self {
+        Self {
+            split: SplitMethod::Median,
+            max_leaf_size: 4,
+            wide: false,
+        }
+    }
+}
+
+/// BVH as a tree of boxed nodes. Rendering uses the flattened `LinearBvh`; the tree is
+/// what gets built, and is kept as the reference the linear layout is benchmarked against.
 pub struct BvhNode {
     pub aabb: AABB,
-    pub left: Arc<dyn Hittable + Send + Sync>,
-    pub right: Arc<dyn Hittable + Send + Sync>,
+    kind: NodeKind,
+}
+
+enum NodeKind {
+    Leaf(Vec<Arc<dyn Hittable + Send + Sync>>),
+    /// Children below and above the split along `axis`.
+    Interior { left: Box<BvhNode>, right: Box<BvhNode>, axis: usize },
+}
+
+/// An object waiting to be placed in the tree, with its box and centroid computed once.
+struct BuildItem {
+    object: Arc<dyn Hittable + Send + Sync>,
+    aabb: AABB,
+    centroid: Point3,
 }
 
 impl BvhNode {
-    pub fn from_list(mut list: Vec<Box<dyn Hittable + Send + Sync>>) -> Arc<BvhNode> {
+    pub fn from_list(list: Vec<Box<dyn Hittable + Send + Sync>>, config: BvhConfig) -> Arc<BvhNode> {
+        BvhNode::from_shared(list.into_iter().map(Arc::from).collect(), config)
+    }
+
+    /// Like `from_list`, for objects that are also referenced elsewhere.
+    pub fn from_shared(list: Vec<Arc<dyn Hittable + Send + Sync>>, config: BvhConfig) -> Arc<BvhNode> {
         assert!(!list.is_empty(), "List must not be empty!");
-        if list.len() == 1 {
-            // Create a degenerate node with both children being the same object
-            let obj: Arc<dyn Hittable + Send + Sync> = Arc::from(list.remove(0));
-            let aabb = obj.get_aabb();
-            let node = BvhNode {
-                aabb: AABB::new(*aabb.axis_interval(0), *aabb.axis_interval(1), *aabb.axis_interval(2)),
-                left: Arc::clone(&obj),
-                right: obj,
+        let mut items: Vec<BuildItem> = list
+            .into_par_iter()
+            .map(|object| {
+                let aabb = *object.get_aabb();
+                BuildItem {
+                    object,
+                    aabb,
+                    centroid: centroid(&aabb),
+                }
+            })
+            .collect();
+        Arc::new(BvhNode::build(&mut items, config, 1))
+    }
+
+    /// Build the subtree over `items`, reordering them in place.
+    fn build(items: &mut [BuildItem], config: BvhConfig, depth: usize) -> BvhNode {
+        let count = items.len();
+        let fits_leaf = count <= config.max_leaf_size.max(1);
+        let median_depth = depth + count.next_power_of_two().trailing_zeros() as usize;
+        let split = if median_depth >= MAX_DEPTH { SplitMethod::Median } else { config.split };
+        let split = match split {
+            _ if count == 1 => None,
+            SplitMethod::Median if fits_leaf => None,
+            SplitMethod::Median => Some(median_split(items)),
+            SplitMethod::Sah => match sah_split(items) {
+                // A leaf is cheaper than any split
+                Some((cost, _, _)) if fits_leaf && cost >= INTERSECTION_COST * count as f64 => None,
+                Some((_, axis, mid)) => Some((axis, mid)),
+                // All centroids in one bin on every axis: nothing to choose, split in half
+                None if fits_leaf => None,
+                None => Some(median_split(items)),
+            },
+        };
+        let Some((axis, mid)) = split else {
+            let aabb = items
+                .iter()
+                .fold(items[0].aabb, |aabb, item| AABB::merge(&aabb, &item.aabb));
+            let objects = items.iter().map(|item| Arc::clone(&item.object)).collect();
+            return BvhNode {
+                aabb,
+                kind: NodeKind::Leaf(objects),
             };
-            return Arc::new(node);
+        };
+
+        let parallel = items.len() >= PARALLEL_THRESHOLD;
+        let (left_items, right_items) = items.split_at_mut(mid);
+        let (left, right) = if parallel {
+            rayon::join(
+                || BvhNode::build(left_items, config, depth + 1),
+                || BvhNode::build(right_items, config, depth + 1),
+            )
+        } else {
+            (
+                BvhNode::build(left_items, config, depth + 1),
+                BvhNode::build(right_items, config, depth + 1),
+            )
+        };
+        BvhNode {
+            aabb: AABB::merge(&left.aabb, &right.aabb),
+            kind: NodeKind::Interior {
+                left: Box::new(left),
+                right: Box::new(right),
+                axis,
+            },
         }
+    }
+
+    /// Shape and expected cost of the tree.
+    pub fn stats(&self) -> BvhStats {
+        let mut stats = BvhStats {
+            nodes: 0,
+            leaves: 0,
+            depth: 0,
+            min_leaf_size: usize::MAX,
+            max_leaf_size: 0,
+            primitives: 0,
+            sah_cost: 0.0,
+        };
+        let root_area = self.aabb.surface_area().max(f64::MIN_POSITIVE);
+        self.collect_stats(1, root_area, &mut stats);
+        stats
+    }
 
-        // Compute the overall bounding box for the list
-        let mut global = AABB::new(AxisInterval::EMPTY, AxisInterval::EMPTY, AxisInterval::EMPTY);
-        for obj in &list {
-            global = AABB::merge(&global, obj.get_aabb());
+    fn collect_stats(&self, depth: usize, root_area: f64, stats: &mut BvhStats) {
+        stats.nodes += 1;
+        stats.depth = stats.depth.max(depth);
+        // Chance that a ray hitting the root also hits this node
+        let probability = self.aabb.surface_area() / root_area;
+        match &self.kind {
+            NodeKind::Leaf(objects) => {
+                let size = objects.len();
+                stats.leaves += 1;
+                stats.primitives += size;
+                stats.min_leaf_size = stats.min_leaf_size.min(size);
+                stats.max_leaf_size = stats.max_leaf_size.max(size);
+                stats.sah_cost += probability * INTERSECTION_COST * size as f64;
+            }
+            NodeKind::Interior { left, right, .. } => {
+                stats.sah_cost += probability * TRAVERSAL_COST;
+                left.collect_stats(depth + 1, root_area, stats);
+                right.collect_stats(depth + 1, root_area, stats);
+            }
         }
+    }
+}
 
-        // Determine the longest axis: 0=x,1=y,2=z
-        let x_len = global.axis_interval(0).end - global.axis_interval(0).start;
-        let y_len = global.axis_interval(1).end - global.axis_interval(1).start;
-        let z_len = global.axis_interval(2).end - global.axis_interval(2).start;
-        let split_axis = if x_len >= y_len && x_len >= z_len {
-            0
-        } else if y_len >= z_len {
-            1
+/// Tree quality metrics; the SAH cost is the expected cost of tracing a ray that hits
+/// the root box, in units of one primitive intersection.
+#[derive(Debug)]
+pub struct BvhStats {
+    pub nodes: usize,
+    pub leaves: usize,
+    pub depth: usize,
+    pub min_leaf_size: usize,
+    pub max_leaf_size: usize,
+    pub primitives: usize,
+    pub sah_cost: f64,
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes, {} leaves, depth {}, leaf size {}..{} (mean {:.2}), SAH cost {:.2}",
+            self.nodes,
+            self.leaves,
+            self.depth,
+            self.min_leaf_size,
+            self.max_leaf_size,
+            self.primitives as f64 / self.leaves as f64,
+            self.sah_cost
+        )
+    }
+}
+
+/// Split at the median centroid along the longest axis. Moves the lower half of `items`
+/// to the front, without sorting either half, and returns the axis and split index.
+fn median_split(items: &mut [BuildItem]) -> (usize, usize) {
+    // Compute the overall bounding box for the list
+    let mut global = AABB::new(AxisInterval::EMPTY, AxisInterval::EMPTY, AxisInterval::EMPTY);
+    for item in items.iter() {
+        global = AABB::merge(&global, &item.aabb);
+    }
+
+    // Determine the longest axis: 0=x,1=y,2=z
+    let x_len = global.axis_interval(0).end - global.axis_interval(0).start;
+    let y_len = global.axis_interval(1).end - global.axis_interval(1).start;
+    let z_len = global.axis_interval(2).end - global.axis_interval(2).start;
+    let split_axis = if x_len >= y_len && x_len >= z_len {
+        0
+    } else if y_len >= z_len {
+        1
+    } else {
+        2
+    };
+
+    let mid = items.len() / 2;
+    items.select_nth_unstable_by(mid, |a, b| a.centroid[split_axis].total_cmp(&b.centroid[split_axis]));
+    (split_axis, mid)
+}
+
+/// Bin the centroids along each axis and split at the bin boundary with the lowest SAH
+/// cost. Moves the objects left of the split to the front of `items` and returns the
+/// cost, axis and split index; `None` if the centroids cannot be separated.
+fn sah_split(items: &mut [BuildItem]) -> Option<(f64, usize, usize)> {
+    let empty = || AABB::new(AxisInterval::EMPTY, AxisInterval::EMPTY, AxisInterval::EMPTY);
+    let mut parent = empty();
+    let mut centroid_min = Point3::repeat(f64::INFINITY);
+    let mut centroid_max = Point3::repeat(f64::NEG_INFINITY);
+    for item in items.iter() {
+        parent = AABB::merge(&parent, &item.aabb);
+        centroid_min = centroid_min.inf(&item.centroid);
+        centroid_max = centroid_max.sup(&item.centroid);
+    }
+    let parent_area = parent.surface_area().max(f64::MIN_POSITIVE);
+
+    // (cost, axis, last bin on the left)
+    let mut best: Option<(f64, usize, usize)> = None;
+    for axis in 0..3 {
+        let (start, extent) = (centroid_min[axis], centroid_max[axis] - centroid_min[axis]);
+        if extent <= 0.0 {
+            continue;
+        }
+
+        let mut counts = [0usize; BIN_COUNT];
+        let mut boxes: [AABB; BIN_COUNT] = std::array::from_fn(|_| empty());
+        for item in items.iter() {
+            let bin = bin_index(item.centroid[axis], start, extent);
+            counts[bin] += 1;
+            boxes[bin] = AABB::merge(&boxes[bin], &item.aabb);
+        }
+
+        // Area times count of everything left of each boundary, swept from the left
+        let mut left_cost = [0.0; BIN_COUNT - 1];
+        let (mut count, mut bounds) = (0, empty());
+        for bin in 0..BIN_COUNT - 1 {
+            count += counts[bin];
+            bounds = AABB::merge(&bounds, &boxes[bin]);
+            left_cost[bin] = if count > 0 { count as f64 * bounds.surface_area() } else { f64::NAN };
+        }
+
+        // ... and right of it, swept from the right, evaluating each boundary on the way
+        let (mut count, mut bounds) = (0, empty());
+        for bin in (1..BIN_COUNT).rev() {
+            count += counts[bin];
+            bounds = AABB::merge(&bounds, &boxes[bin]);
+            let left = left_cost[bin - 1];
+            if count == 0 || left.is_nan() {
+                continue;
+            }
+            let right = count as f64 * bounds.surface_area();
+            let cost = TRAVERSAL_COST + INTERSECTION_COST * (left + right) / parent_area;
+            if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                best = Some((cost, axis, bin - 1));
+            }
+        }
+    }
+
+    let (cost, axis, split) = best?;
+    let (start, extent) = (centroid_min[axis], centroid_max[axis] - centroid_min[axis]);
+    let mut mid = 0;
+    for i in 0..items.len() {
+        if bin_index(items[i].centroid[axis], start, extent) <= split {
+            items.swap(i, mid);
+            mid += 1;
+        }
+    }
+    Some((cost, axis, mid))
+}
+
+fn bin_index(value: f64, start: f64, extent: f64) -> usize {
+    (((value - start) / extent * BIN_COUNT as f64) as usize).min(BIN_COUNT - 1)
+}
+
+fn centroid(aabb: &AABB) -> Point3 {
+    Point3::from_fn(|axis, _| {
+        let interval = aabb.axis_interval(axis);
+        0.5 * (interval.start + interval.end)
+    })
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        match &self.kind {
+            NodeKind::Leaf(objects) => {
+                let mut closest_so_far = interval.end;
+                let mut hit_record = None;
+                for object in objects {
+                    if let Some(record) = object.hit(ray, &Interval::new(interval.start, closest_so_far)) {
+                        closest_so_far = record.t;
+                        hit_record = Some(record);
+                    }
+                }
+                hit_record
+            }
+            NodeKind::Interior { left, right, .. } => {
+                if !self.aabb.hit(ray, interval) {
+                    return None;
+                }
+                let record_left = left.hit(ray, interval);
+                let record_right = right.hit(ray, interval);
+                match (record_left, record_right) {
+                    (Some(l), Some(r)) => {
+                        if l.t <= r.t { Some(l) } else { Some(r) }
+                    }
+                    (record_left, record_right) => record_left.or(record_right),
+                }
+            }
+        }
+    }
+
+    fn get_aabb(&self) -> &AABB { &self.aabb }
+}
+
+/// The BVH layout used for rendering, binary or wide as chosen by `BvhConfig::wide`.
+pub enum Bvh {
+    Linear(LinearBvh),
+    Wide(WideBvh),
+}
+
+impl Bvh {
+    pub fn new(tree: &BvhNode, config: BvhConfig) -> Bvh {
+        if config.wide {
+            Bvh::Wide(WideBvh::new(tree))
         } else {
-            2
+            Bvh::Linear(LinearBvh::new(tree))
+        }
+    }
+
+    pub fn from_list(list: Vec<Box<dyn Hittable + Send + Sync>>, config: BvhConfig) -> Bvh {
+        Bvh::new(&BvhNode::from_list(list, config), config)
+    }
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        match self {
+            Bvh::Linear(bvh) => bvh.hit(ray, interval),
+            Bvh::Wide(bvh) => bvh.hit(ray, interval),
+        }
+    }
+
+    fn get_aabb(&self) -> &AABB {
+        match self {
+            Bvh::Linear(bvh) => bvh.get_aabb(),
+            Bvh::Wide(bvh) => bvh.get_aabb(),
+        }
+    }
+}
+
+/// BVH flattened into an array in depth-first order: the first child of a node follows
+/// it directly, and leaves refer to a range of `primitives`. Traversal is iterative and
+/// visits the nearer child first, so farther subtrees are often culled by the closest hit.
+pub struct LinearBvh {
+    nodes: Vec<LinearNode>,
+    primitives: Vec<Arc<dyn Hittable + Send + Sync>>,
+}
+
+struct LinearNode {
+    aabb: AABB,
+    /// First primitive for leaves, the second child for interior nodes.
+    offset: u32,
+    /// Number of primitives; zero for interior nodes.
+    count: u16,
+    axis: u8,
+}
+
+impl LinearBvh {
+    pub fn new(tree: &BvhNode) -> LinearBvh {
+        let mut bvh = LinearBvh {
+            nodes: Vec::new(),
+            primitives: Vec::new(),
         };
+        bvh.flatten(tree);
+        bvh
+    }
 
-        // Sort along the chosen axis using the AABB centers
-        list.sort_by(|a, b| {
-            let aa = a.get_aabb().axis_interval(split_axis);
-            let ba = b.get_aabb().axis_interval(split_axis);
-            let ac = 0.5 * (aa.start + aa.end);
-            let bc = 0.5 * (ba.start + ba.end);
-            ac.partial_cmp(&bc).unwrap_or(std::cmp::Ordering::Equal)
+    fn flatten(&mut self, node: &BvhNode) {
+        let index = self.nodes.len();
+        self.nodes.push(LinearNode {
+            aabb: node.aabb,
+            offset: 0,
+            count: 0,
+            axis: 0,
         });
+        match &node.kind {
+            NodeKind::Leaf(objects) => {
+                self.nodes[index].offset = self.primitives.len() as u32;
+                self.nodes[index].count = u16::try_from(objects.len()).expect("BVH leaf too large");
+                self.primitives.extend(objects.iter().map(Arc::clone));
+            }
+            NodeKind::Interior { left, right, axis } => {
+                self.flatten(left);
+                self.nodes[index].offset = self.nodes.len() as u32;
+                self.nodes[index].axis = *axis as u8;
+                self.flatten(right);
+            }
+        }
+    }
+}
 
-        let mid = list.len() / 2;
-        let right_list = list.split_off(mid);
-        let left_list = list; // the remaining left half
+impl Hittable for LinearBvh {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        let mut stack = [0u32; MAX_DEPTH];
+        let mut top = 0;
+        let mut index = 0;
+        let mut closest = interval.end;
+        let mut hit_record = None;
+        loop {
+            let node = &self.nodes[index];
+            if node.aabb.hit(ray, &Interval::new(interval.start, closest)) {
+                if node.count > 0 {
+                    let first = node.offset as usize;
+                    for object in &self.primitives[first..first + node.count as usize] {
+                        if let Some(record) = object.hit(ray, &Interval::new(interval.start, closest)) {
+                            closest = record.t;
+                            hit_record = Some(record);
+                        }
+                    }
+                } else {
+                    // Visit the child on the side the ray comes from, keep the other for later
+                    let (near, far) = if ray.direction[node.axis as usize] < 0.0 {
+                        (node.offset, index as u32 + 1)
+                    } else {
+                        (index as u32 + 1, node.offset)
+                    };
+                    stack[top] = far;
+                    top += 1;
+                    index = near as usize;
+                    continue;
+                }
+            }
+            if top == 0 {
+                return hit_record;
+            }
+            top -= 1;
+            index = stack[top] as usize;
+        }
+    }
 
-        let left = BvhNode::from_list(left_list);
-        let right = BvhNode::from_list(right_list);
-        let aabb = AABB::merge(left.get_aabb(), right.get_aabb());
+    fn get_aabb(&self) -> &AABB {
+        &self.nodes[0].aabb
+    }
+}
 
-        Arc::new(BvhNode {
-            aabb,
-            left: left as Arc<dyn Hittable + Send + Sync>,
-            right: right as Arc<dyn Hittable + Send + Sync>
-        })
+/// BVH with four children per node, collapsed from the binary tree. The four child boxes
+/// are stored as SIMD lanes and tested against a ray at once, which halves the depth of
+/// the tree and the number of traversal steps.
+pub struct WideBvh {
+    nodes: Vec<WideNode>,
+    primitives: Vec<Arc<dyn Hittable + Send + Sync>>,
+    aabb: AABB,
+}
+
+/// Child boxes in structure of arrays layout, one lane per child. Unused lanes hold an
+/// empty box that no ray hits.
+struct WideNode {
+    min: [f64x4; 3],
+    max: [f64x4; 3],
+    children: [WideChild; 4],
+}
+
+#[derive(Clone, Copy)]
+enum WideChild {
+    Empty,
+    Node(u32),
+    Leaf { offset: u32, count: u16 },
+}
+
+impl WideBvh {
+    pub fn new(tree: &BvhNode) -> WideBvh {
+        let mut bvh = WideBvh {
+            nodes: Vec::new(),
+            primitives: Vec::new(),
+            aabb: tree.aabb,
+        };
+        bvh.collapse(tree);
+        bvh
+    }
+
+    /// Append the wide node for `node` and its subtree, returning its index.
+    fn collapse(&mut self, node: &BvhNode) -> u32 {
+        // Open up the largest interior child until there are four children
+        let mut children = match &node.kind {
+            NodeKind::Leaf(_) => vec![node],
+            NodeKind::Interior { left, right, .. } => vec![left.as_ref(), right.as_ref()],
+        };
+        while children.len() < 4 {
+            let largest = children
+                .iter()
+                .enumerate()
+                .filter(|(_, child)| matches!(child.kind, NodeKind::Interior { .. }))
+                .max_by(|(_, a), (_, b)| a.aabb.surface_area().total_cmp(&b.aabb.surface_area()))
+                .map(|(index, _)| index);
+            let Some(index) = largest else { break };
+            let NodeKind::Interior { left, right, .. } = &children[index].kind else {
+                unreachable!("only interior children are opened")
+            };
+            children.splice(index..=index, [left.as_ref(), right.as_ref()]);
+        }
+
+        let index = self.nodes.len();
+        self.nodes.push(WideNode {
+            min: [f64x4::splat(f64::INFINITY); 3],
+            max: [f64x4::splat(f64::NEG_INFINITY); 3],
+            children: [WideChild::Empty; 4],
+        });
+
+        let mut min = [[f64::INFINITY; 4]; 3];
+        let mut max = [[f64::NEG_INFINITY; 4]; 3];
+        let mut slots = [WideChild::Empty; 4];
+        for (lane, child) in children.into_iter().enumerate() {
+            for (axis, (min, max)) in min.iter_mut().zip(max.iter_mut()).enumerate() {
+                min[lane] = child.aabb.axis_interval(axis).start;
+                max[lane] = child.aabb.axis_interval(axis).end;
+            }
+            slots[lane] = match &child.kind {
+                NodeKind::Leaf(objects) => {
+                    let offset = self.primitives.len() as u32;
+                    self.primitives.extend(objects.iter().map(Arc::clone));
+                    let count = u16::try_from(objects.len()).expect("BVH leaf too large");
+                    WideChild::Leaf { offset, count }
+                }
+                NodeKind::Interior { .. } => WideChild::Node(self.collapse(child)),
+            };
+        }
+
+        let node = &mut self.nodes[index];
+        node.min = min.map(f64x4::new);
+        node.max = max.map(f64x4::new);
+        node.children = slots;
+        index as u32
     }
 }
 
-impl Hittable for BvhNode {
+impl Hittable for WideBvh {
     fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
-        if !self.aabb.hit(ray, interval) {
-            return None;
-        }
-        let record_left = self.left.hit(ray, interval);
-        let record_right = self.right.hit(ray, interval);
+        let origin = [0, 1, 2].map(|axis| f64x4::splat(ray.origin[axis]));
+        let inv_direction = [0, 1, 2].map(|axis| f64x4::splat(ray.inv_direction[axis]));
 
-        match (record_left, record_right) {
-            (Some(l), Some(r)) => {
-                if l.t <= r.t { Some(l) } else { Some(r) }
+        // Every node visited leaves at most three siblings behind
+        let mut stack = [WideChild::Empty; 3 * MAX_DEPTH + 1];
+        stack[0] = WideChild::Node(0);
+        let mut top = 1;
+        let mut closest = interval.end;
+        let mut hit_record = None;
+        while top > 0 {
+            top -= 1;
+            match stack[top] {
+                WideChild::Empty => {}
+                WideChild::Leaf { offset, count } => {
+                    let first = offset as usize;
+                    for object in &self.primitives[first..first + count as usize] {
+                        if let Some(record) = object.hit(ray, &Interval::new(interval.start, closest)) {
+                            closest = record.t;
+                            hit_record = Some(record);
+                        }
+                    }
+                }
+                WideChild::Node(index) => {
+                    let node = &self.nodes[index as usize];
+                    // The same slab test as `AABB::hit`, on all four boxes at once
+                    let mut t_min = f64x4::splat(interval.start);
+                    let mut t_max = f64x4::splat(closest);
+                    for axis in 0..3 {
+                        let (near, far) = if ray.sign[axis] == 0 {
+                            (node.min[axis], node.max[axis])
+                        } else {
+                            (node.max[axis], node.min[axis])
+                        };
+                        t_min = ((near - origin[axis]) * inv_direction[axis]).max(t_min);
+                        t_max = ((far - origin[axis]) * inv_direction[axis]).min(t_max);
+                    }
+                    let mask = t_min.cmp_le(t_max).move_mask();
+                    if mask == 0 {
+                        continue;
+                    }
+
+                    // Push the hit children farthest first, so the nearest is visited next
+                    let entry = t_min.to_array();
+                    let mut lanes = [0usize; 4];
+                    let mut hits = 0;
+                    for lane in (0..4).filter(|lane| mask & (1 << lane) != 0) {
+                        lanes[hits] = lane;
+                        hits += 1;
+                    }
+                    lanes[..hits].sort_unstable_by(|&a, &b| entry[b].total_cmp(&entry[a]));
+                    for &lane in &lanes[..hits] {
+                        stack[top] = node.children[lane];
+                        top += 1;
+                    }
+                }
             }
-            (Some(l), None) => Some(l),
-            (None, Some(r)) => Some(r),
-            (None, None) => None,
         }
+        hit_record
     }
 
-    fn get_aabb(&self) -> &AABB { &self.aabb }
-}
\ No newline at end of file
+    fn get_aabb(&self) -> &AABB {
+        &self.aabb
+    }
+}
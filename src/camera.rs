@@ -1,8 +1,11 @@
 use crate::common::*;
 use crate::hittable::{Hittable, Interval};
+use crate::integrator::Integrator;
+use crate::light::LightList;
 use crate::ray::Ray;
 use indicatif::ProgressBar;
-use rand::rngs::ThreadRng;
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
 use rayon::prelude::*;
 
 #[derive(Debug)]
@@ -10,37 +13,170 @@ pub struct Camera {
     width: usize,
     height: usize,
     sample_per_pixel: usize,
-    max_depth: usize,
     center: Point3,
     pixel00_loc: Point3,
     pixel_delta_u: Vec3,
     pixel_delta_v: Vec3,
-    defocus_angel: f64,
+    defocus_angle: f64,
     defocus_u: Vec3,
     defocus_v: Vec3,
+    seed: u64,
+    shutter: Interval,
+    /// Bytes of the rendered RGB image, checked not to overflow by `build`.
+    image_size: usize,
 }
 
-impl Camera {
-    pub fn new(aspect_ratio: f64, width: usize) -> Self {
-        let height = ((width as f64) / aspect_ratio) as usize;
-        let height = height.max(1);
+/// Builder for [`Camera`]. Settings are plain public fields that scene files and command
+/// line options override in place; `build` validates them and derives the viewport.
+#[derive(Debug, Clone)]
+pub struct CameraBuilder {
+    pub aspect_ratio: f64,
+    pub width: usize,
+    /// Explicit image height; `None` derives it from `aspect_ratio`.
+    pub height: Option<usize>,
+    pub sample_per_pixel: usize,
+    pub vertical_fov: f64,
+    pub look_from: Point3,
+    pub look_at: Point3,
+    pub up: Vec3,
+    /// Aperture expressed as the cone angle (degrees) of rays through each pixel.
+    pub defocus_angle: f64,
+    pub focus_distance: f64,
+    /// Base seed of the per-pixel random generators; `None` picks a random one.
+    pub seed: Option<u64>,
+    /// Exposure interval rays are spread over; objects move over times 0 to 1.
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraError(pub String);
+
+impl std::fmt::Display for CameraError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid camera: {}", self.0)
+    }
+}
+
+impl std::error::Error for CameraError {}
+
+impl Default for CameraBuilder {
+    fn default() -> Self {
+        Self {
+            aspect_ratio: 16.0 / 9.0,
+            width: 1920,
+            height: None,
+            sample_per_pixel: 16,
+            vertical_fov: 20.0,
+            look_from: Point3::new(13.0, 2.0, 3.0),
+            look_at: Point3::new(0.0, 0.0, 0.0),
+            up: Vec3::new(0.0, 1.0, 0.0),
+            defocus_angle: 0.6,
+            focus_distance: 10.0,
+            seed: None,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+        }
+    }
+}
+
+impl CameraBuilder {
+    fn image_height(&self) -> usize {
+        let height = self
+            .height
+            .unwrap_or(((self.width as f64) / self.aspect_ratio) as usize);
+        height.max(1)
+    }
+
+    /// Bytes of the RGB image, `None` if the count overflows `usize`.
+    fn image_size(&self) -> Option<usize> {
+        self.width
+            .checked_mul(self.image_height())
+            .and_then(|pixels| pixels.checked_mul(3))
+    }
+
+    pub fn validate(&self) -> Result<(), CameraError> {
+        let fail = |message: String| Err(CameraError(message));
+
+        if self.width == 0 || self.height == Some(0) {
+            return fail("image dimensions must be greater than zero".to_string());
+        }
+        if !(self.aspect_ratio.is_finite() && self.aspect_ratio > 0.0) {
+            return fail(format!("aspect ratio must be positive, got {}", self.aspect_ratio));
+        }
+        if self.image_size().is_none() {
+            return fail(format!(
+                "image of {}x{} pixels is too large",
+                self.width,
+                self.image_height()
+            ));
+        }
+        if self.sample_per_pixel == 0 {
+            return fail("samples per pixel must be greater than zero".to_string());
+        }
+        if !(self.vertical_fov > 0.0 && self.vertical_fov < 180.0) {
+            return fail(format!(
+                "vertical fov must be in (0, 180) degrees, got {}",
+                self.vertical_fov
+            ));
+        }
+        if !(self.defocus_angle >= 0.0 && self.defocus_angle < 180.0) {
+            return fail(format!(
+                "defocus angle must be in [0, 180) degrees, got {}",
+                self.defocus_angle
+            ));
+        }
+        if !(self.focus_distance.is_finite() && self.focus_distance > 0.0) {
+            return fail(format!(
+                "focus distance must be positive, got {}",
+                self.focus_distance
+            ));
+        }
+
+        if !(self.shutter_open.is_finite()
+            && self.shutter_close.is_finite()
+            && self.shutter_open <= self.shutter_close)
+        {
+            return fail(format!(
+                "shutter must open before it closes, got {} to {}",
+                self.shutter_open, self.shutter_close
+            ));
+        }
 
-        let sample_per_pixel = 16;
-        let max_depth = 4;
+        let view = self.look_at - self.look_from;
+        if near_zero(&view) {
+            return fail("look_from and look_at must be different points".to_string());
+        }
+        if near_zero(&self.up) {
+            return fail("up vector must be non-zero".to_string());
+        }
+        if near_zero(&self.up.normalize().cross(&view.normalize())) {
+            return fail("up vector must not be parallel to the view direction".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn build(&self) -> Result<Camera, CameraError> {
+        self.validate()?;
 
-        let defocus_angel = 0.6;
-        let focus_distance = 10.0;
+        let width = self.width;
+        let height = self.image_height();
+        let image_size = self.image_size().expect("validated above");
 
-        let vertical_fov = 20.0;
-        let theta = vertical_fov * PI / 180.0;
+        let sample_per_pixel = self.sample_per_pixel;
+
+        let defocus_angle = self.defocus_angle;
+        let focus_distance = self.focus_distance;
+
+        let theta = self.vertical_fov * PI / 180.0;
         let half_height = (theta / 2.0).tan();
 
         let viewport_height = 2.0 * half_height * focus_distance;
         let viewport_width = viewport_height * (width as f64 / height as f64);
 
-        let look_from = Point3::new(13.0, 2.0, 3.0);
-        let look_at = Point3::new(0.0, 0.0, 0.0);
-        let up = Vec3::new(0.0, 1.0, 0.0);
+        let look_from = self.look_from;
+        let look_at = self.look_at;
+        let up = self.up;
 
         let center = look_from;
 
@@ -57,62 +193,49 @@ impl Camera {
         let viewport_upper_left = center - focus_distance * w - viewport_u / 2.0 - viewport_v / 2.0;
         let pixel00_loc = viewport_upper_left + 0.5 * (pixel_delta_u + pixel_delta_v);
 
-        let defocus_radius = focus_distance * (defocus_angel / 2.0 / 180.0 * PI).tan();
+        let defocus_radius = focus_distance * (defocus_angle / 2.0 / 180.0 * PI).tan();
         let defocus_u = defocus_radius * u;
         let defocus_v = defocus_radius * v;
 
-        Self {
+        let seed = self.seed.unwrap_or_else(rand::random);
+
+        Ok(Camera {
             width,
             height,
             sample_per_pixel,
-            max_depth,
             center,
             pixel00_loc,
             pixel_delta_u,
             pixel_delta_v,
-            defocus_angel,
+            defocus_angle,
             defocus_u,
             defocus_v,
-        }
-    }
-
-    pub fn ray_color(
-        &self,
-        ray: &Ray,
-        depth: usize,
-        world: &impl Hittable,
-        rng: &mut ThreadRng,
-    ) -> Color {
-        if depth >= self.max_depth {
-            return Color::new(0.0, 0.0, 0.0);
-        }
-
-        match world.hit(ray, &Interval::new(0.001, f64::INFINITY)) {
-            Some(hit_record) => match hit_record.material.scatter(ray, &hit_record, rng) {
-                Some((scattered, attenuation)) => {
-                    attenuation.component_mul(&self.ray_color(&scattered, depth + 1, world, rng))
-                }
-                None => Color::new(0.0, 0.0, 0.0),
-            },
-            None => {
-                let unit_direction = &ray.direction; // 直接就是 normalized 的
-                let t = 0.5 * (unit_direction.y + 1.0);
-                (1.0 - t) * Color::new(1.0, 1.0, 1.0) + t * Color::new(0.5, 0.7, 1.0)
-            }
-        }
+            seed,
+            shutter: Interval::new(self.shutter_open, self.shutter_close),
+            image_size,
+        })
     }
+}
 
+impl Camera {
     fn linear_to_gamma(&self, color: Color) -> Color {
         color.map(|c| if c > 0.0 { c.sqrt() } else { 0.0 })
     }
 
-    pub fn write_color(&self, pixel_buffer: &mut [u8], color: Color) -> () {
-        pixel_buffer[0] = (color[0] * 255.999) as u8;
-        pixel_buffer[1] = (color[1] * 255.999) as u8;
-        pixel_buffer[2] = (color[2] * 255.999) as u8;
+    pub fn write_color(&self, pixel_buffer: &mut [u8], color: Color) {
+        let intensity = Interval::new(0.0, 0.999);
+        pixel_buffer[0] = (intensity.clamp(color[0]) * 256.0) as u8;
+        pixel_buffer[1] = (intensity.clamp(color[1]) * 256.0) as u8;
+        pixel_buffer[2] = (intensity.clamp(color[2]) * 256.0) as u8;
+    }
+
+    /// Image size in pixels, width then height.
+    pub fn resolution(&self) -> (usize, usize) {
+        (self.width, self.height)
     }
 
-    fn get_ray(&self, i: usize, j: usize, rng: &mut ThreadRng) -> Ray {
+    /// A random ray through pixel (`i`, `j`).
+    pub fn get_ray(&self, i: usize, j: usize, rng: &mut SmallRng) -> Ray {
         let ru: f64 = random_range(-0.5, 0.5, rng);
         let rv: f64 = random_range(-0.5, 0.5, rng);
         let pixel_sample = self.pixel00_loc
@@ -121,22 +244,27 @@ impl Camera {
             + ru * self.pixel_delta_u
             + rv * self.pixel_delta_v;
 
-        let ray_origin = if self.defocus_angel <= 0.0 {
+        let ray_origin = if self.defocus_angle <= 0.0 {
             self.center
         } else {
             self.defocus_disk_sample(rng)
         };
         let ray_direction = pixel_sample - ray_origin;
-        Ray::new(ray_origin, ray_direction)
+        let ray_time = if self.shutter.size() > 0.0 {
+            random_range(self.shutter.start, self.shutter.end, rng)
+        } else {
+            self.shutter.start
+        };
+        Ray::new(ray_origin, ray_direction, ray_time)
     }
 
-    pub fn render(&self, world: &(impl Hittable + Sync)) -> (usize, usize, Vec<u8>) {
-        let size = self
-            .width
-            .checked_mul(self.height)
-            .and_then(|px| px.checked_mul(3))
-            .expect("width*height*3 overflowed");
-        let mut buffer: Vec<u8> = vec![0u8; size];
+    pub fn render(
+        &self,
+        world: &(impl Hittable + Sync),
+        lights: &LightList,
+        integrator: &dyn Integrator,
+    ) -> (usize, usize, Vec<u8>) {
+        let mut buffer: Vec<u8> = vec![0u8; self.image_size];
 
         let bar = ProgressBar::new((self.width * self.height) as u64);
 
@@ -145,14 +273,22 @@ impl Camera {
         buffer.par_chunks_mut(3).enumerate().for_each(|(idx, pix)| {
             let j = idx / width;
             let i = idx % width;
-            let mut rng = rand::rng();
+            // Seed per pixel so a fixed seed reproduces the image regardless of scheduling
+            let mut rng = SmallRng::seed_from_u64(
+                self.seed ^ (idx as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+            );
 
             let mut color = Color::new(0.0, 0.0, 0.0);
             for _ in 0..self.sample_per_pixel {
-                color += self.ray_color(&self.get_ray(i, j, &mut rng), 0, world, &mut rng);
+                let ray = self.get_ray(i, j, &mut rng);
+                color += integrator.ray_color(&ray, world, lights, &mut rng);
             }
             let color = color / self.sample_per_pixel as f64;
-            let color = self.linear_to_gamma(color);
+            let color = if integrator.is_radiance() {
+                self.linear_to_gamma(color)
+            } else {
+                color
+            };
             self.write_color(pix, color);
             bar.inc(1);
         });
@@ -161,7 +297,7 @@ impl Camera {
         (self.width, self.height, buffer)
     }
 
-    fn defocus_disk_sample(&self, rng: &mut ThreadRng) -> Vec3 {
+    fn defocus_disk_sample(&self, rng: &mut SmallRng) -> Vec3 {
         let p = random_in_unit_disk(rng);
         self.center + self.defocus_u * p.x + self.defocus_v * p.y
     }
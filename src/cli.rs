@@ -0,0 +1,184 @@
+use crate::bvh::{BvhConfig, SplitMethod, MAX_LEAF_SIZE};
+use crate::integrator::IntegratorKind;
+use crate::light::MisHeuristic;
+use crate::write_img::OutputFormat;
+use std::fmt;
+use std::str::FromStr;
+
+pub const USAGE: &str = "\
+Usage: rt-rs [OPTIONS] [SCENE]
+
+Render SCENE (a scene description file) or, without one, the random book cover scene.
+
+Options:
+  -s, --scene <PATH>      Scene file to render
+  -o, --output <PATH>     Output image [default: output/bvh.jpg]
+  -f, --format <FORMAT>   Output format: jpg, png or ppm [default: from the output extension]
+  -w, --width <N>         Image width in pixels
+      --height <N>        Image height in pixels (overrides the scene aspect ratio)
+      --spp <N>           Samples per pixel
+      --max-depth <N>     Maximum number of ray bounces
+      --roulette <N|off>  Russian roulette after N bounces, or off to stop only at --max-depth [default: 3]
+  -j, --threads <N>       Number of render threads [default: all cores]
+      --seed <N>          Seed for reproducible renders
+      --mis <HEURISTIC>   Weighting of light & BSDF samples: power, balance or none [default: power]
+      --integrator <NAME> What to render: path, normals or ao (ambient occlusion) [default: path]
+      --ao-distance <D>   How far occluders count for ao [default: a tenth of the scene size]
+      --bvh <METHOD>      BVH split method: median or sah [default: median]
+      --max-leaf-size <N> Most objects in a BVH leaf, at most 65535 [default: 4]
+      --wide-bvh          Use 4-wide BVH nodes tested with SIMD
+      --bench-bvh         Time BVH traversal (and TLAS rebuilds) instead of rendering
+  -h, --help              Print this help
+";
+
+/// Render settings from the command line. `None` keeps the value from the scene.
+#[derive(Debug, Default)]
+pub struct Options {
+    pub scene: Option<String>,
+    pub output: String,
+    pub format: Option<OutputFormat>,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub sample_per_pixel: Option<usize>,
+    pub max_depth: Option<usize>,
+    /// `Some(None)` turns Russian roulette off.
+    pub roulette_depth: Option<Option<usize>>,
+    pub threads: Option<usize>,
+    pub seed: Option<u64>,
+    pub mis: Option<MisHeuristic>,
+    pub integrator: Option<IntegratorKind>,
+    pub ao_distance: Option<f64>,
+    pub bvh: BvhConfig,
+    pub bench_bvh: bool,
+    pub help: bool,
+}
+
+#[derive(Debug)]
+pub struct CliError(pub String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl Options {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, CliError> {
+        let mut options = Options {
+            output: "output/bvh.jpg".to_string(),
+            ..Default::default()
+        };
+
+        while let Some(arg) = args.next() {
+            // Accept both `--flag value` and `--flag=value`
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+            let mut value = || -> Result<String, CliError> {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| CliError(format!("{} requires a value", flag)))
+            };
+
+            match flag.as_str() {
+                "-h" | "--help" => options.help = true,
+                "--bench-bvh" => options.bench_bvh = true,
+                "--wide-bvh" => options.bvh.wide = true,
+                "--max-leaf-size" => {
+                    let size = positive(&flag, &value()?)?;
+                    if size > MAX_LEAF_SIZE {
+                        return Err(CliError(format!("{}: must be at most {}", flag, MAX_LEAF_SIZE)));
+                    }
+                    options.bvh.max_leaf_size = size;
+                }
+                "-s" | "--scene" => options.scene = Some(value()?),
+                "-o" | "--output" => options.output = value()?,
+                "-f" | "--format" => {
+                    let name = value()?;
+                    let format = OutputFormat::from_name(&name)
+                        .ok_or_else(|| CliError(format!("unknown output format '{}'", name)))?;
+                    options.format = Some(format);
+                }
+                "-w" | "--width" => options.width = Some(number(&flag, &value()?)?),
+                "--height" => options.height = Some(number(&flag, &value()?)?),
+                "--spp" => options.sample_per_pixel = Some(number(&flag, &value()?)?),
+                "--max-depth" => options.max_depth = Some(number(&flag, &value()?)?),
+                "--roulette" => {
+                    let value = value()?;
+                    options.roulette_depth = Some(match value.as_str() {
+                        "off" => None,
+                        depth => Some(number(&flag, depth)?),
+                    });
+                }
+                "-j" | "--threads" => options.threads = Some(positive(&flag, &value()?)?),
+                "--seed" => options.seed = Some(number(&flag, &value()?)?),
+                "--mis" => {
+                    let name = value()?;
+                    let heuristic = MisHeuristic::from_name(&name)
+                        .ok_or_else(|| CliError(format!("unknown MIS heuristic '{}'", name)))?;
+                    options.mis = Some(heuristic);
+                }
+                "--integrator" => {
+                    let name = value()?;
+                    let integrator = IntegratorKind::from_name(&name)
+                        .ok_or_else(|| CliError(format!("unknown integrator '{}'", name)))?;
+                    options.integrator = Some(integrator);
+                }
+                "--ao-distance" => {
+                    let distance: f64 = number(&flag, &value()?)?;
+                    if !(distance.is_finite() && distance > 0.0) {
+                        return Err(CliError(format!("{}: must be positive", flag)));
+                    }
+                    options.ao_distance = Some(distance);
+                }
+                "--bvh" => {
+                    let name = value()?;
+                    options.bvh.split = SplitMethod::from_name(&name)
+                        .ok_or_else(|| CliError(format!("unknown BVH split method '{}'", name)))?;
+                }
+                _ if flag.starts_with('-') => {
+                    return Err(CliError(format!("unknown option '{}'", flag)));
+                }
+                _ => {
+                    if options.scene.is_some() {
+                        return Err(CliError(format!("unexpected argument '{}'", arg)));
+                    }
+                    options.scene = Some(arg);
+                }
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// The explicit `--format`, else the one implied by the output path.
+    pub fn output_format(&self) -> Result<OutputFormat, CliError> {
+        match self.format {
+            Some(format) => Ok(format),
+            None => OutputFormat::from_path(&self.output).ok_or_else(|| {
+                CliError(format!(
+                    "cannot infer the image format of '{}', use --format",
+                    self.output
+                ))
+            }),
+        }
+    }
+}
+
+fn number<T: FromStr>(flag: &str, value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError(format!("{}: invalid value '{}'", flag, value)))
+}
+
+fn positive(flag: &str, value: &str) -> Result<usize, CliError> {
+    let n: usize = number(flag, value)?;
+    if n == 0 {
+        return Err(CliError(format!("{}: must be greater than zero", flag)));
+    }
+    Ok(n)
+}
@@ -1,4 +1,4 @@
-use rand::rngs::ThreadRng;
+use rand::rngs::SmallRng;
 use rand::Rng;
 use rand_distr::{Distribution, Normal};
 pub use std::f64::consts::PI;
@@ -7,19 +7,19 @@ pub type Point3 = nalgebra::Vector3<f64>;
 pub type Vec3 = nalgebra::Vector3<f64>;
 pub type Color = nalgebra::Vector3<f64>;
 
-pub fn random(rng: &mut ThreadRng) -> f64 {
+pub fn random(rng: &mut SmallRng) -> f64 {
     rng.random_range(0.0..=1.0)
 }
 
-pub fn random_range(min: f64, max: f64, rng: &mut ThreadRng) -> f64 {
+pub fn random_range(min: f64, max: f64, rng: &mut SmallRng) -> f64 {
     rng.random_range(min..=max)
 }
 
-pub fn random_vec3(rng: &mut ThreadRng) -> Vec3 {
+pub fn random_vec3(rng: &mut SmallRng) -> Vec3 {
     Vec3::new(random(rng), random(rng), random(rng))
 }
 
-pub fn random_range_vec3(min: f64, max: f64, rng: &mut ThreadRng) -> Vec3 {
+pub fn random_range_vec3(min: f64, max: f64, rng: &mut SmallRng) -> Vec3 {
     Vec3::new(
         random_range(min, max, rng),
         random_range(min, max, rng),
@@ -27,7 +27,7 @@ pub fn random_range_vec3(min: f64, max: f64, rng: &mut ThreadRng) -> Vec3 {
     )
 }
 
-pub fn random_unit_vec3(rng: &mut ThreadRng) -> Vec3 {
+pub fn random_unit_vec3(rng: &mut SmallRng) -> Vec3 {
     let normal_dist = Normal::new(0.0, 1.0).unwrap();
     let x = normal_dist.sample(rng);
     let y = normal_dist.sample(rng);
@@ -35,7 +35,23 @@ pub fn random_unit_vec3(rng: &mut ThreadRng) -> Vec3 {
     Vec3::new(x, y, z).normalize()
 }
 
-pub fn random_in_unit_disk(rng: &mut ThreadRng) -> Vec3 {
+/// Direction in the hemisphere around +z with density proportional to its z component,
+/// together with that density, see `cosine_pdf`.
+pub fn random_cosine_direction(rng: &mut SmallRng) -> (Vec3, f64) {
+    let phi = 2.0 * PI * random(rng);
+    let r2 = random(rng);
+    let z = (1.0 - r2).sqrt();
+    let direction = Vec3::new(phi.cos() * r2.sqrt(), phi.sin() * r2.sqrt(), z);
+    (direction, cosine_pdf(z))
+}
+
+/// Solid angle density of cosine weighted sampling for a direction at `cos_theta` to the
+/// axis; zero below the hemisphere.
+pub fn cosine_pdf(cos_theta: f64) -> f64 {
+    cos_theta.max(0.0) / PI
+}
+
+pub fn random_in_unit_disk(rng: &mut SmallRng) -> Vec3 {
     let theta = 2.0 * PI * random(rng);
     let r = random(rng).sqrt();
     Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
@@ -0,0 +1,82 @@
+use crate::aabb::AABB;
+use crate::common::*;
+use crate::hittable::{HitRecord, Hittable, Interval};
+use crate::material::Material;
+use crate::ray::{self, Ray};
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use std::sync::Arc;
+
+/// Fog or smoke of uniform density filling a closed `boundary`. A ray travelling through
+/// it scatters after an exponentially distributed distance, using the `phase_function`.
+pub struct ConstantMedium {
+    boundary: Arc<dyn Hittable + Send + Sync>,
+    neg_inv_density: f64,
+    phase_function: Arc<dyn Material>,
+    /// Mixed into the per-ray seed; see `Ray::seed`.
+    salt: u64,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Arc<dyn Hittable + Send + Sync>,
+        density: f64,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function,
+            salt: ray::next_salt(),
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        let (start, end) = boundary_segment(self.boundary.as_ref(), ray, interval)?;
+
+        // Ray directions are normalized, so t is a distance
+        let distance_inside = end - start;
+        let mut rng = SmallRng::seed_from_u64(ray.seed(self.salt));
+        let hit_distance = self.neg_inv_density * random(&mut rng).max(f64::MIN_POSITIVE).ln();
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        Some(scatter_record(ray, start + hit_distance, &self.phase_function))
+    }
+
+    fn get_aabb(&self) -> &AABB {
+        self.boundary.get_aabb()
+    }
+}
+
+/// The part of `interval` where `ray` is inside the closed `boundary`, as `(start, end)`.
+pub(crate) fn boundary_segment(
+    boundary: &(dyn Hittable + Send + Sync),
+    ray: &Ray,
+    interval: &Interval,
+) -> Option<(f64, f64)> {
+    // Where the ray enters and leaves the boundary, wherever its origin lies
+    let enter = boundary.hit(ray, &Interval::UNIVERSE)?;
+    let leave = boundary.hit(ray, &Interval::new(enter.t + 0.0001, f64::INFINITY))?;
+
+    let start = enter.t.max(interval.start).max(0.0);
+    let end = leave.t.min(interval.end);
+    (start < end).then_some((start, end))
+}
+
+/// Hit record for a scattering event inside a medium at distance `t`.
+pub(crate) fn scatter_record(ray: &Ray, t: f64, phase_function: &Arc<dyn Material>) -> HitRecord {
+    HitRecord {
+        point: ray.at(t),
+        normal: Vec3::new(1.0, 0.0, 0.0), // arbitrary
+        t,
+        u: 0.0,
+        v: 0.0,
+        material: Arc::clone(phase_function),
+        front_face: true, // also arbitrary
+        on_light: false,
+    }
+}
@@ -0,0 +1,48 @@
+use crate::aabb::AABB;
+use crate::common::*;
+use crate::hittable::{HitRecord, Hittable, Interval};
+use crate::hittable_list::HittableList;
+use crate::material::Material;
+use crate::quad::Quad;
+use crate::ray::Ray;
+use std::sync::Arc;
+
+/// Axis-aligned box made of six quads (named to stay clear of `std::boxed::Box`).
+pub struct Cuboid {
+    sides: HittableList,
+}
+
+impl Cuboid {
+    /// The box with opposite corners `a` and `b`.
+    pub fn new(a: &Point3, b: &Point3, material: Arc<dyn Material>) -> Self {
+        let min = a.inf(b);
+        let max = a.sup(b);
+
+        let dx = Vec3::new(max.x - min.x, 0.0, 0.0);
+        let dy = Vec3::new(0.0, max.y - min.y, 0.0);
+        let dz = Vec3::new(0.0, 0.0, max.z - min.z);
+
+        let mut sides = HittableList::new();
+        let mut side = |q: Point3, u: Vec3, v: Vec3| {
+            sides.add(Box::new(Quad::new(q, u, v, Arc::clone(&material))));
+        };
+        side(Point3::new(min.x, min.y, max.z), dx, dy); // front
+        side(Point3::new(max.x, min.y, max.z), -dz, dy); // right
+        side(Point3::new(max.x, min.y, min.z), -dx, dy); // back
+        side(Point3::new(min.x, min.y, min.z), dz, dy); // left
+        side(Point3::new(min.x, max.y, max.z), dx, -dz); // top
+        side(Point3::new(min.x, min.y, min.z), dx, dz); // bottom
+
+        Self { sides }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        self.sides.hit(ray, interval)
+    }
+
+    fn get_aabb(&self) -> &AABB {
+        self.sides.get_aabb()
+    }
+}
@@ -0,0 +1,80 @@
+use crate::aabb::AABB;
+use crate::common::*;
+use crate::hittable::{HitRecord, Hittable, Interval};
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use std::sync::Arc;
+
+pub struct Disk {
+    pub center: Point3,
+    pub normal: Vec3,
+    pub radius: f64,
+    pub material: Arc<dyn Material>,
+    // Orthonormal in-plane axes, for the polar UV coordinates
+    tangent: Vec3,
+    bitangent: Vec3,
+    aabb: AABB,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64, material: Arc<dyn Material>) -> Self {
+        let normal = normal.normalize();
+        let basis = Onb::new(&normal);
+        let (tangent, bitangent) = (basis.u, basis.v);
+
+        // A tilted disk reaches radius * sin(angle between axis and normal) along each axis
+        let extent = normal.map(|n| radius * (1.0 - n * n).max(0.0).sqrt());
+        let aabb = AABB::from_point(&(center - extent), &(center + extent)).pad_to_minimums();
+
+        Self {
+            center,
+            normal,
+            radius,
+            material,
+            tangent,
+            bitangent,
+            aabb,
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        let denominator = self.normal.dot(&ray.direction);
+        // Ray parallel to the plane
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = self.normal.dot(&(self.center - ray.origin)) / denominator;
+        if t < interval.start || t > interval.end {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let offset = point - self.center;
+        let r = offset.norm();
+        if r > self.radius {
+            return None;
+        }
+
+        let phi = offset.dot(&self.bitangent).atan2(offset.dot(&self.tangent));
+        let front_face = ray.direction.dot(&self.normal) < 0.0;
+        let normal = if front_face { self.normal } else { -self.normal };
+        Some(HitRecord {
+            point,
+            normal,
+            t,
+            u: (phi + PI) / (2.0 * PI),
+            v: r / self.radius,
+            material: Arc::clone(&self.material),
+            front_face,
+            on_light: false,
+        })
+    }
+
+    fn get_aabb(&self) -> &AABB {
+        &self.aabb
+    }
+}
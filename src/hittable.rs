@@ -2,6 +2,7 @@ use crate::common::*;
 pub use crate::interval::Interval;
 use crate::material::Material;
 use crate::ray::Ray;
+use rand::rngs::SmallRng;
 use std::sync::Arc;
 use crate::aabb::AABB;
 
@@ -9,11 +10,64 @@ pub struct HitRecord {
     pub point: Point3,
     pub normal: Vec3,
     pub t: f64,
+    /// Surface coordinates of the hit, consumed by textures.
+    pub u: f64,
+    pub v: f64,
     pub material: Arc<dyn Material>,
     pub front_face: bool,
+    /// Whether the object hit is one of the sampled lights, see `light::Light`.
+    pub on_light: bool,
 }
 
 pub trait Hittable {
     fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord>;
     fn get_aabb(&self) -> &AABB;
+
+    /// Solid angle density of `random` picking `direction` from `origin`; zero for
+    /// objects that cannot be sampled as lights.
+    fn pdf_value(&self, _origin: &Point3, _direction: &Vec3) -> f64 {
+        0.0
+    }
+
+    /// A direction from `origin` toward a random point on the object.
+    fn random(&self, _origin: &Point3, _rng: &mut SmallRng) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
+}
+
+/// Shared objects are hittable too, e.g. one mesh referenced by several instances.
+impl<T: Hittable + ?Sized> Hittable for Arc<T> {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        self.as_ref().hit(ray, interval)
+    }
+
+    fn get_aabb(&self) -> &AABB {
+        self.as_ref().get_aabb()
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        self.as_ref().pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut SmallRng) -> Vec3 {
+        self.as_ref().random(origin, rng)
+    }
+}
+
+/// Monte Carlo check of a light's sampling: the integral of `pdf_value` from `origin` over
+/// all directions, which should be about 1, and the share of `random` directions that
+/// `pdf_value` gives a non-zero density, which should be about all of them.
+#[cfg(test)]
+pub(crate) fn check_light_pdf(object: &dyn Hittable, origin: &Point3, samples: usize) -> (f64, f64) {
+    use rand::SeedableRng;
+
+    let mut rng = SmallRng::seed_from_u64(7);
+    let total: f64 = (0..samples)
+        .map(|_| object.pdf_value(origin, &random_unit_vec3(&mut rng)))
+        .sum();
+    let integral = 4.0 * PI * total / samples as f64;
+    let covered = (0..samples)
+        .filter(|_| object.pdf_value(origin, &object.random(origin, &mut rng)) > 0.0)
+        .count();
+    (integral, covered as f64 / samples as f64)
 }
@@ -0,0 +1,327 @@
+use crate::aabb::AABB;
+use crate::background::{Background, GradientBackground};
+use crate::common::*;
+use crate::hittable::{HitRecord, Hittable, Interval};
+use crate::light::{LightList, MisHeuristic};
+use crate::onb::Onb;
+use crate::ray::Ray;
+use rand::rngs::SmallRng;
+use rand::Rng;
+use std::fmt;
+use std::sync::Arc;
+
+/// Turns a camera ray into the color of one pixel sample.
+pub trait Integrator: Send + Sync + fmt::Debug {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &dyn Hittable,
+        lights: &LightList,
+        rng: &mut SmallRng,
+    ) -> Color;
+
+    /// Whether `ray_color` is radiance, to be gamma encoded for display; false for
+    /// debug outputs that already are display values.
+    fn is_radiance(&self) -> bool {
+        true
+    }
+}
+
+/// The integrators selectable at run time.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum IntegratorKind {
+    #[default]
+    Path,
+    Normals,
+    AmbientOcclusion,
+}
+
+impl IntegratorKind {
+    pub fn from_name(name: &str) -> Option<IntegratorKind> {
+        match name.to_ascii_lowercase().as_str() {
+            "path" => Some(IntegratorKind::Path),
+            "normals" => Some(IntegratorKind::Normals),
+            "ao" => Some(IntegratorKind::AmbientOcclusion),
+            _ => None,
+        }
+    }
+}
+
+/// Settings for the `Integrator` a render uses. Like `CameraBuilder`, they are plain
+/// public fields that scene files and command line options override in place.
+#[derive(Debug, Clone)]
+pub struct IntegratorConfig {
+    pub kind: IntegratorKind,
+    /// Hard limit on bounces; with Russian roulette only a safety cap.
+    pub max_depth: usize,
+    /// Bounces after which paths are ended at random by Russian roulette; `None` cuts
+    /// them off at `max_depth` only.
+    pub roulette_depth: Option<usize>,
+    /// How light samples and BSDF samples reaching the same light are combined.
+    pub mis: MisHeuristic,
+    /// Radiance of rays that escape the scene.
+    pub background: Arc<dyn Background>,
+    /// How far occluders count for ambient occlusion; `None` derives it from the scene size.
+    pub ao_distance: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegratorError(pub String);
+
+impl fmt::Display for IntegratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid integrator settings: {}", self.0)
+    }
+}
+
+impl std::error::Error for IntegratorError {}
+
+impl Default for IntegratorConfig {
+    fn default() -> Self {
+        Self {
+            kind: IntegratorKind::default(),
+            max_depth: 50,
+            roulette_depth: Some(3),
+            mis: MisHeuristic::default(),
+            background: Arc::new(GradientBackground::sky()),
+            ao_distance: None,
+        }
+    }
+}
+
+impl IntegratorConfig {
+    pub fn validate(&self) -> Result<(), IntegratorError> {
+        if self.max_depth == 0 {
+            return Err(IntegratorError("max depth must be greater than zero".to_string()));
+        }
+        if let Some(distance) = self.ao_distance
+            && !(distance.is_finite() && distance > 0.0)
+        {
+            return Err(IntegratorError(format!("ao distance must be positive, got {}", distance)));
+        }
+        Ok(())
+    }
+
+    /// `scene_bounds` scales settings that default to a share of the scene size.
+    pub fn build(&self, scene_bounds: &AABB) -> Result<Box<dyn Integrator>, IntegratorError> {
+        self.validate()?;
+        Ok(match self.kind {
+            IntegratorKind::Path => Box::new(PathIntegrator {
+                max_depth: self.max_depth,
+                roulette_depth: self.roulette_depth,
+                mis: self.mis,
+                background: Arc::clone(&self.background),
+            }),
+            IntegratorKind::Normals => Box::new(NormalsIntegrator),
+            IntegratorKind::AmbientOcclusion => {
+                Box::new(AmbientOcclusion::new(self.ao_distance, scene_bounds))
+            }
+        })
+    }
+}
+
+/// Unidirectional path tracing with next-event estimation, MIS and Russian roulette.
+#[derive(Debug)]
+pub struct PathIntegrator {
+    /// Hard limit on bounces; with Russian roulette only a safety cap.
+    pub max_depth: usize,
+    /// Bounces after which paths are ended at random; `None` cuts them off at
+    /// `max_depth` only.
+    pub roulette_depth: Option<usize>,
+    pub mis: MisHeuristic,
+    /// Radiance of rays that escape the scene.
+    pub background: Arc<dyn Background>,
+}
+
+impl Integrator for PathIntegrator {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &dyn Hittable,
+        lights: &LightList,
+        rng: &mut SmallRng,
+    ) -> Color {
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let mut radiance = Color::zeros();
+        // Product of the scattering weights from the camera up to `ray`
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray = *ray;
+        // Density with which the last hit picked `ray`, if that hit also sampled the
+        // lights directly; emission of a light reached by `ray` is then weighted against
+        // the light sample
+        let mut bsdf_pdf: Option<f64> = None;
+
+        for depth in 0..self.max_depth {
+            let Some(hit_record) = world.hit(&ray, &interval) else {
+                radiance += throughput.component_mul(&self.background.value(&ray.direction));
+                break;
+            };
+            let material = &hit_record.material;
+            let mut emitted = material.emitted(&hit_record);
+            if let Some(bsdf_pdf) = bsdf_pdf
+                && hit_record.on_light
+            {
+                let light_pdf = lights.pdf_value(&ray.origin, &ray.direction);
+                emitted *= 1.0 - self.mis.light_weight(light_pdf, bsdf_pdf);
+            }
+            radiance += throughput.component_mul(&emitted);
+
+            // Next-event estimation: light reaching the hit straight from a point sampled
+            // on the lights. Done before scattering, as a glossy BSDF may still reflect
+            // light toward the camera when the direction it picks is absorbed.
+            let sample_lights = !material.is_specular() && !lights.is_empty();
+            if sample_lights && depth + 1 < self.max_depth {
+                let direct = self.sample_lights(&ray, &hit_record, world, lights, rng);
+                radiance += throughput.component_mul(&direct);
+            }
+
+            let Some((scattered, attenuation)) = material.scatter(&ray, &hit_record, rng) else {
+                break;
+            };
+            let weight = if sample_lights {
+                // Whatever arrives along the scattered direction
+                let pdf = material.pdf(&ray, &hit_record, &scattered.direction);
+                if pdf <= 0.0 {
+                    break;
+                }
+                bsdf_pdf = Some(pdf);
+                material.eval(&ray, &hit_record, &scattered.direction) / pdf
+            } else {
+                bsdf_pdf = None;
+                attenuation
+            };
+            throughput = throughput.component_mul(&weight);
+
+            // Russian roulette: end dim paths at random and boost the survivors to make
+            // up for them, which keeps the estimate unbiased
+            if let Some(roulette_depth) = self.roulette_depth
+                && depth >= roulette_depth
+            {
+                let survival = throughput.max().min(1.0);
+                // Half open, so paths with full survival are never ended
+                if rng.random::<f64>() >= survival {
+                    break;
+                }
+                throughput /= survival;
+            }
+            ray = scattered;
+        }
+        radiance
+    }
+}
+
+impl PathIntegrator {
+    /// Light from a point picked on `lights`, if nothing blocks the way to it.
+    fn sample_lights(
+        &self,
+        ray: &Ray,
+        hit_record: &HitRecord,
+        world: &dyn Hittable,
+        lights: &LightList,
+        rng: &mut SmallRng,
+    ) -> Color {
+        let direction = lights.random(&hit_record.point, rng);
+        let shadow_ray = Ray::new(hit_record.point, direction, ray.time);
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let Some(light_record) = world.hit(&shadow_ray, &interval) else {
+            return Color::zeros();
+        };
+        if !light_record.on_light {
+            return Color::zeros();
+        }
+        // Divide by the density over all lights, since any of them could have been
+        // picked to give this direction
+        let light_pdf = lights.pdf_value(&hit_record.point, &shadow_ray.direction);
+        if light_pdf <= 0.0 {
+            return Color::zeros();
+        }
+        let material = &hit_record.material;
+        let bsdf_pdf = material.pdf(ray, hit_record, &shadow_ray.direction);
+        let weight = self.mis.light_weight(light_pdf, bsdf_pdf);
+        let emitted = light_record.material.emitted(&light_record);
+        let bsdf = material.eval(ray, hit_record, &shadow_ray.direction);
+        weight * bsdf.component_mul(&emitted) / light_pdf
+    }
+}
+
+/// Outward surface normal of the first hit mapped to a color, black for misses.
+#[derive(Debug)]
+pub struct NormalsIntegrator;
+
+impl Integrator for NormalsIntegrator {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &dyn Hittable,
+        _lights: &LightList,
+        _rng: &mut SmallRng,
+    ) -> Color {
+        match world.hit(ray, &Interval::new(0.001, f64::INFINITY)) {
+            Some(hit_record) => {
+                let normal = if hit_record.front_face {
+                    hit_record.normal
+                } else {
+                    -hit_record.normal
+                };
+                0.5 * (normal + Color::new(1.0, 1.0, 1.0))
+            }
+            None => Color::zeros(),
+        }
+    }
+
+    fn is_radiance(&self) -> bool {
+        false
+    }
+}
+
+/// Ambient occlusion: the share of a cosine weighted hemisphere above the first hit that
+/// is open up to `distance`, white where nothing is hit.
+#[derive(Debug)]
+pub struct AmbientOcclusion {
+    /// How far occluders count.
+    pub distance: f64,
+}
+
+impl AmbientOcclusion {
+    /// Occluders within `distance`, or by default a tenth of the diagonal of the scene's
+    /// bounding box.
+    pub fn new(distance: Option<f64>, scene_bounds: &AABB) -> Self {
+        let distance = distance.unwrap_or_else(|| {
+            let diagonal = Vec3::new(
+                scene_bounds.axis_interval(0).size(),
+                scene_bounds.axis_interval(1).size(),
+                scene_bounds.axis_interval(2).size(),
+            );
+            0.1 * diagonal.norm()
+        });
+        Self { distance }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &dyn Hittable,
+        _lights: &LightList,
+        rng: &mut SmallRng,
+    ) -> Color {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let Some(hit_record) = world.hit(ray, &Interval::new(0.001, f64::INFINITY)) else {
+            return white;
+        };
+
+        // One probe per sample; the pixel average gives the occlusion
+        let (local, _) = random_cosine_direction(rng);
+        let direction = Onb::new(&hit_record.normal).to_world(&local);
+        let probe = Ray::new(hit_record.point, direction, ray.time);
+        match world.hit(&probe, &Interval::new(0.001, self.distance)) {
+            Some(_) => Color::zeros(),
+            None => white,
+        }
+    }
+
+    fn is_radiance(&self) -> bool {
+        false
+    }
+}
@@ -40,6 +40,10 @@ impl Interval {
         x
     }
 
+    pub fn size(&self) -> f64 {
+        self.end - self.start
+    }
+
     pub fn expand(&self, delta: f64) -> Interval {
         let padding = delta / 2.0;
         Interval::new(
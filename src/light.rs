@@ -0,0 +1,133 @@
+use crate::aabb::AABB;
+use crate::common::*;
+use crate::hittable::{HitRecord, Hittable, Interval};
+use crate::ray::Ray;
+use rand::rngs::SmallRng;
+use rand::Rng;
+use std::sync::Arc;
+
+/// Emitters sampled directly for next-event estimation. Each is also part of the world,
+/// wrapped in a `Light` so hits on it can be told apart; the list only shares them.
+#[derive(Default)]
+pub struct LightList {
+    lights: Vec<Arc<dyn Hittable + Send + Sync>>,
+}
+
+impl LightList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, light: Arc<dyn Hittable + Send + Sync>) {
+        self.lights.push(light);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    /// A direction from `origin` toward a light picked uniformly from the list.
+    pub fn random(&self, origin: &Point3, rng: &mut SmallRng) -> Vec3 {
+        let index = rng.random_range(0..self.lights.len());
+        self.lights[index].random(origin, rng)
+    }
+
+    /// Density of `random` picking `direction`, whichever light it came from.
+    pub fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let total: f64 = self
+            .lights
+            .iter()
+            .map(|light| light.pdf_value(origin, direction))
+            .sum();
+        total / self.lights.len() as f64
+    }
+
+}
+
+/// An emitter that is also in a `LightList`; marks its hit records with `on_light`.
+pub struct Light<H: Hittable> {
+    object: H,
+}
+
+impl<H: Hittable> Light<H> {
+    pub fn new(object: H) -> Self {
+        Self { object }
+    }
+}
+
+impl<H: Hittable> Hittable for Light<H> {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        let mut record = self.object.hit(ray, interval)?;
+        record.on_light = true;
+        Some(record)
+    }
+
+    fn get_aabb(&self) -> &AABB {
+        self.object.get_aabb()
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        self.object.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut SmallRng) -> Vec3 {
+        self.object.random(origin, rng)
+    }
+}
+
+/// How a light sample and a BSDF sample that can both reach the same light are weighted
+/// against each other (multiple importance sampling).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MisHeuristic {
+    /// Listed lights are reached by light samples only, BSDF samples ignore them.
+    None,
+    Balance,
+    #[default]
+    Power,
+}
+
+impl MisHeuristic {
+    pub fn from_name(name: &str) -> Option<MisHeuristic> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(MisHeuristic::None),
+            "balance" => Some(MisHeuristic::Balance),
+            "power" => Some(MisHeuristic::Power),
+            _ => None,
+        }
+    }
+
+    /// Weight of the light sample given the densities of both strategies for its
+    /// direction; the BSDF sample gets one minus this.
+    pub fn light_weight(self, light_pdf: f64, bsdf_pdf: f64) -> f64 {
+        let (light, bsdf) = match self {
+            MisHeuristic::None => return 1.0,
+            MisHeuristic::Balance => (light_pdf, bsdf_pdf),
+            MisHeuristic::Power => (light_pdf * light_pdf, bsdf_pdf * bsdf_pdf),
+        };
+        if light + bsdf > 0.0 { light / (light + bsdf) } else { 1.0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mis_weights_sum_to_one() {
+        let pdfs = [0.0, 0.01, 0.3, 1.0, 2.5, 40.0];
+        for heuristic in [MisHeuristic::None, MisHeuristic::Balance, MisHeuristic::Power] {
+            for light_pdf in pdfs {
+                for bsdf_pdf in pdfs {
+                    let light = heuristic.light_weight(light_pdf, bsdf_pdf);
+                    assert!((0.0..=1.0).contains(&light), "{:?}: {}", heuristic, light);
+                    // The BSDF sample of the same direction sees the densities swapped, and
+                    // its weight is the complement of this one
+                    if heuristic != MisHeuristic::None && light_pdf + bsdf_pdf > 0.0 {
+                        let swapped = heuristic.light_weight(bsdf_pdf, light_pdf);
+                        assert!((light + swapped - 1.0).abs() < 1e-12, "{:?}", heuristic);
+                    }
+                }
+            }
+        }
+    }
+}
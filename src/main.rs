@@ -1,101 +1,175 @@
+mod background;
+mod bench;
 mod camera;
+mod cli;
 mod common;
+mod constant_medium;
+mod cuboid;
+mod disk;
 mod hittable;
 mod hittable_list;
+mod integrator;
 mod interval;
+mod light;
 mod material;
+mod mesh;
+mod obj;
+mod onb;
+mod perlin;
+mod quad;
 mod ray;
 mod sphere;
+mod texture;
+mod tlas;
+mod transform;
+mod triangle;
+mod volume;
 mod write_img;
 mod aabb;
 mod bvh;
+mod scene;
 
-use crate::hittable_list::HittableList;
-use crate::material::Material;
-use camera::Camera;
-use common::*;
-use sphere::Sphere;
-use std::sync::Arc;
-use write_img::write_jpg;
-use crate::bvh::BvhNode;
+use cli::{Options, USAGE};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+use scene::Scene;
+use std::time::Instant;
+use write_img::write_image;
+use crate::bvh::{Bvh, BvhNode};
+use crate::aabb::AABB;
+use crate::hittable::Hittable;
 
 fn main() {
-    // World
-    let mut world = HittableList::new();
-
-    let material_ground: Arc<dyn Material> =
-        Arc::new(material::Lambertian::new(Color::new(0.5, 0.5, 0.5)));
-    world.add(Box::new(Sphere::new(
-        Point3::new(0.0, -1000.0, 0.0),
-        1000.0,
-        Arc::clone(&material_ground),
-    )));
-
-    let mut rng = rand::rngs::ThreadRng::default();
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = random(&mut rng);
-            let center = Point3::new(
-                a as f64 + 0.9 * random(&mut rng),
-                0.2,
-                b as f64 + 0.9 * random(&mut rng),
-            );
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        print!("{}", USAGE);
+        return;
+    }
+    let format = match options.output_format() {
+        Ok(format) => format,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        }
+    };
 
-            if (center - Point3::new(4.0, 0.2, 0.0)).norm() > 0.9 {
-                let material: Arc<dyn Material>;
+    if let Some(threads) = options.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .expect("the global thread pool is only configured once");
+    }
 
-                if choose_mat < 0.8 {
-                    // diffuse
-                    let albedo = random_vec3(&mut rng).component_mul(&random_vec3(&mut rng));
-                    material = Arc::new(material::Lambertian::new(albedo));
-                    world.add(Box::new(Sphere::new(center, 0.2, material)));
-                } else if choose_mat < 0.95 {
-                    // metal
-                    let albedo = random_range_vec3(0.5, 1.0, &mut rng);
-                    let fuzz = random_range(0.0, 0.5, &mut rng);
-                    material = Arc::new(material::Metal::new(albedo, fuzz));
-                    world.add(Box::new(Sphere::new(center, 0.2, material)));
-                } else {
-                    // glass
-                    material = Arc::new(material::Dielectric::new(1.5));
-                    world.add(Box::new(Sphere::new(center, 0.2, material)));
-                }
+    // World
+    let load_start = Instant::now();
+    let scene = match &options.scene {
+        Some(path) => match Scene::load(path, options.bvh) {
+            Ok(scene) => {
+                println!("Loaded {} in {:.1} ms", path, load_start.elapsed().as_secs_f64() * 1e3);
+                scene
+            }
+            Err(e) => {
+                eprintln!("Failed to load scene {}: {}", path, e);
+                std::process::exit(1);
             }
+        },
+        None => {
+            let mut rng = match options.seed {
+                Some(seed) => SmallRng::seed_from_u64(seed),
+                None => SmallRng::from_os_rng(),
+            };
+            Scene::book_cover(&mut rng)
         }
-    }
+    };
 
-    let material_1: Arc<dyn Material> = Arc::new(material::Dielectric::new(1.5));
-    world.add(Box::new(Sphere::new(
-        Point3::new(0.0, 1.0, 0.0),
-        1.0,
-        Arc::clone(&material_1),
-    )));
+    // Command line settings take precedence over the scene file
+    let mut builder = scene.camera;
+    if let Some(width) = options.width {
+        builder.width = width;
+    }
+    if let Some(height) = options.height {
+        if options.width.is_none() {
+            builder.width = ((height as f64) * builder.aspect_ratio).round().max(1.0) as usize;
+        }
+        builder.height = Some(height);
+    }
+    if let Some(spp) = options.sample_per_pixel {
+        builder.sample_per_pixel = spp;
+    }
+    if options.seed.is_some() {
+        builder.seed = options.seed;
+    }
+    let mut settings = scene.integrator;
+    if let Some(max_depth) = options.max_depth {
+        settings.max_depth = max_depth;
+    }
+    if let Some(roulette_depth) = options.roulette_depth {
+        settings.roulette_depth = roulette_depth;
+    }
+    if let Some(mis) = options.mis {
+        settings.mis = mis;
+    }
+    if let Some(kind) = options.integrator {
+        settings.kind = kind;
+    }
+    if options.ao_distance.is_some() {
+        settings.ao_distance = options.ao_distance;
+    }
 
-    let material_2: Arc<dyn Material> =
-        Arc::new(material::Lambertian::new(Color::new(0.4, 0.2, 0.1)));
-    world.add(Box::new(Sphere::new(
-        Point3::new(-4.0, 1.0, 0.0),
-        1.0,
-        Arc::clone(&material_2),
-    )));
+    // Camera
+    let camera = match builder.build() {
+        Ok(camera) => camera,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    let material_3: Arc<dyn Material> =
-        Arc::new(material::Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
-    world.add(Box::new(Sphere::new(
-        Point3::new(4.0, 1.0, 0.0),
-        1.0,
-        Arc::clone(&material_3),
-    )));
+    // Integrator
+    let bounds = match &scene.instances {
+        Some(instances) => AABB::merge(scene.world.get_aabb(), instances.get_aabb()),
+        None => *scene.world.get_aabb(),
+    };
+    let integrator = match settings.build(&bounds) {
+        Ok(integrator) => integrator,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    let world = BvhNode::from_list(world.objects);
+    let mut objects = scene.world.objects;
+    if let Some(mut instances) = scene.instances {
+        if options.bench_bvh {
+            bench::bench_tlas(&mut instances);
+        }
+        objects.push(Box::new(instances));
+    }
 
-    // Camera
-    let camera = Camera::new(16.0 / 9.0, 1920);
-    let (width, height, buffer) = camera.render(world.as_ref());
-    let file_name = "output/bvh.jpg";
-    match write_jpg(file_name, width, height, &buffer, 100) {
+    let build_start = Instant::now();
+    let tree = BvhNode::from_list(objects, options.bvh);
+    eprintln!(
+        "BVH: built in {:.1} ms, {}",
+        build_start.elapsed().as_secs_f64() * 1e3,
+        tree.stats()
+    );
+    if options.bench_bvh {
+        bench::bench_bvh(&camera, &tree, options.seed.unwrap_or(0));
+        return;
+    }
+    // Rendering only needs the flattened nodes; the objects are shared with them
+    let world = Bvh::new(&tree, options.bvh);
+    drop(tree);
+    let (width, height, buffer) = camera.render(&world, &scene.lights, integrator.as_ref());
+    let file_name = &options.output;
+    match write_image(file_name, format, width, height, &buffer) {
         Ok(()) => println!("Wrote {} ({}x{} pixels)", file_name, width, height),
         Err(e) => eprintln!("Failed to write {}: {}", file_name, e),
     }
-    return;
 }
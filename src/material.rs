@@ -1,23 +1,60 @@
-use crate::common::{near_zero, random, random_unit_vec3, reflect, refract, Color};
+use crate::common::{
+    cosine_pdf, random, random_cosine_direction, random_unit_vec3, reflect, refract, Color, Vec3, PI,
+};
 use crate::hittable::HitRecord;
+use crate::onb::Onb;
 use crate::ray::Ray;
-use rand::rngs::ThreadRng;
+use crate::texture::{SolidColor, Texture};
+use rand::rngs::SmallRng;
+use std::sync::Arc;
 
 pub trait Material: Send + Sync {
     fn scatter(
         &self,
         ray_in: &Ray,
         hit_record: &HitRecord,
-        rng: &mut ThreadRng,
+        rng: &mut SmallRng,
     ) -> Option<(Ray, Color)>;
+
+    /// Light given off at the hit point, black for everything but light sources.
+    fn emitted(&self, _hit_record: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// Whether objects made of this material should be sampled as lights.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    /// Whether `eval` and `pdf` are unavailable, as for mirrors and glass whose
+    /// scattering cannot be evaluated for an arbitrary direction. Light sampling is
+    /// skipped at such hits.
+    fn is_specular(&self) -> bool {
+        true
+    }
+
+    /// BSDF (or phase function) times the cosine term for scattering from `ray_in`
+    /// into `direction`.
+    fn eval(&self, _ray_in: &Ray, _hit_record: &HitRecord, _direction: &Vec3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// Solid angle density of `scatter` picking `direction`.
+    fn pdf(&self, _ray_in: &Ray, _hit_record: &HitRecord, _direction: &Vec3) -> f64 {
+        0.0
+    }
 }
 
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Self {
         Self { albedo }
     }
 }
@@ -25,32 +62,44 @@ impl Lambertian {
 impl Material for Lambertian {
     fn scatter(
         &self,
-        _ray_in: &Ray,
+        ray_in: &Ray,
         hit_record: &HitRecord,
-        rng: &mut ThreadRng,
+        rng: &mut SmallRng,
     ) -> Option<(Ray, Color)> {
-        let scatter_direction_sample = hit_record.normal + random_unit_vec3(rng);
+        // Cosine weighted, so the cosine and 1/pi of the BRDF cancel against the density
+        let (local, _) = random_cosine_direction(rng);
+        let scatter_direction = Onb::new(&hit_record.normal).to_world(&local);
 
-        // catch degenerate scatter direction
-        let scatter_direction = if near_zero(&scatter_direction_sample) {
-            hit_record.normal
-        } else {
-            scatter_direction_sample
-        };
-
-        let scattered = Ray::new(hit_record.point, scatter_direction);
-        let attenuation = self.albedo;
+        let scattered = Ray::new(hit_record.point, scatter_direction, ray_in.time);
+        let attenuation = self.albedo.value(hit_record.u, hit_record.v, &hit_record.point);
         Some((scattered, attenuation))
     }
+
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Color {
+        let albedo = self.albedo.value(hit_record.u, hit_record.v, &hit_record.point);
+        albedo * self.pdf(ray_in, hit_record, direction)
+    }
+
+    fn pdf(&self, _ray_in: &Ray, hit_record: &HitRecord, direction: &Vec3) -> f64 {
+        cosine_pdf(hit_record.normal.dot(&direction.normalize()))
+    }
 }
 
 pub struct Metal {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
     pub fuzz: f64,
 }
 
 impl Metal {
-    pub fn new(albedo: Color, _fuzz: f64) -> Self {
+    pub fn new(albedo: Color, fuzz: f64) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>, _fuzz: f64) -> Self {
         let fuzz = if _fuzz < 1.0 { _fuzz } else { 1.0 };
         Self { albedo, fuzz }
     }
@@ -61,18 +110,45 @@ impl Material for Metal {
         &self,
         ray_in: &Ray,
         hit_record: &HitRecord,
-        rng: &mut ThreadRng,
+        rng: &mut SmallRng,
     ) -> Option<(Ray, Color)> {
         let reflected =
             reflect(&ray_in.direction, &hit_record.normal) + self.fuzz * random_unit_vec3(rng);
-        let scattered = Ray::new(hit_record.point, reflected);
-        let attenuation = self.albedo;
+        let scattered = Ray::new(hit_record.point, reflected, ray_in.time);
+        let attenuation = self.albedo.value(hit_record.u, hit_record.v, &hit_record.point);
         if scattered.direction.dot(&hit_record.normal) > 0.0 {
             Some((scattered, attenuation))
         } else {
             None
         }
     }
+    /// Only a perfect mirror; fuzzy reflections are glossy and can be evaluated.
+    fn is_specular(&self) -> bool {
+        self.fuzz <= 0.0
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Color {
+        // `scatter` absorbs directions below the surface
+        if direction.dot(&hit_record.normal) <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let albedo = self.albedo.value(hit_record.u, hit_record.v, &hit_record.point);
+        albedo * self.pdf(ray_in, hit_record, direction)
+    }
+
+    /// `scatter` picks a uniform point on the sphere of radius `fuzz` around the mirror
+    /// direction. Each direction through that sphere meets it twice; the density adds
+    /// up the squared distance over cosine of both points.
+    fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vec3) -> f64 {
+        let fuzz = self.fuzz;
+        let reflected = reflect(&ray_in.direction, &hit_record.normal);
+        let b = direction.normalize().dot(&reflected);
+        let discriminant = b * b - 1.0 + fuzz * fuzz;
+        if fuzz <= 0.0 || b <= 0.0 || discriminant <= 0.0 {
+            return 0.0;
+        }
+        (2.0 * b * b - 1.0 + fuzz * fuzz) / (2.0 * PI * fuzz * discriminant.sqrt())
+    }
 }
 
 pub struct Dielectric {
@@ -97,7 +173,7 @@ impl Material for Dielectric {
         &self,
         ray_in: &Ray,
         hit_record: &HitRecord,
-        rng: &mut ThreadRng,
+        rng: &mut SmallRng,
     ) -> Option<(Ray, Color)> {
         let attenuation = Color::new(1.0, 1.0, 1.0);
         let reflection_index = if hit_record.front_face {
@@ -118,7 +194,233 @@ impl Material for Dielectric {
                 refract(&unit_direction, &hit_record.normal, reflection_index)
             };
 
-        let scattered = Ray::new(hit_record.point, direction);
+        let scattered = Ray::new(hit_record.point, direction, ray_in.time);
+        Some((scattered, attenuation))
+    }
+}
+
+pub struct DiffuseLight {
+    pub emit: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(emit)))
+    }
+
+    pub fn from_texture(emit: Arc<dyn Texture>) -> Self {
+        Self { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _ray_in: &Ray,
+        _hit_record: &HitRecord,
+        _rng: &mut SmallRng,
+    ) -> Option<(Ray, Color)> {
+        None
+    }
+
+    fn emitted(&self, hit_record: &HitRecord) -> Color {
+        self.emit.value(hit_record.u, hit_record.v, &hit_record.point)
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+}
+
+/// Phase function scattering equally in all directions, for participating media.
+pub struct Isotropic {
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        rng: &mut SmallRng,
+    ) -> Option<(Ray, Color)> {
+        let scattered = Ray::new(hit_record.point, random_unit_vec3(rng), ray_in.time);
+        let attenuation = self.albedo.value(hit_record.u, hit_record.v, &hit_record.point);
         Some((scattered, attenuation))
     }
+
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Color {
+        let albedo = self.albedo.value(hit_record.u, hit_record.v, &hit_record.point);
+        albedo * self.pdf(ray_in, hit_record, direction)
+    }
+
+    fn pdf(&self, _ray_in: &Ray, _hit_record: &HitRecord, _direction: &Vec3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
+
+/// Phase function of Henyey and Greenstein. `g` in (-1, 1) is the mean cosine of the
+/// scattering angle: positive scatters forward, negative backward, zero isotropically.
+pub struct HenyeyGreenstein {
+    pub albedo: Arc<dyn Texture>,
+    pub g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn from_texture(albedo: Arc<dyn Texture>, g: f64) -> Self {
+        Self { albedo, g }
+    }
+
+    /// Cosine between the incoming and scattered directions, by inverting the CDF.
+    fn sample_cos_theta(&self, xi: f64) -> f64 {
+        let g = self.g;
+        if g.abs() < 1e-3 {
+            return 1.0 - 2.0 * xi;
+        }
+        let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+        ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+    }
+
+    /// Density of scattering by an angle with cosine `cos_theta`, per unit solid angle.
+    fn phase(&self, cos_theta: f64) -> f64 {
+        let g = self.g;
+        let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        rng: &mut SmallRng,
+    ) -> Option<(Ray, Color)> {
+        let cos_theta = self.sample_cos_theta(random(rng));
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random(rng);
+
+        let local = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+        let direction = Onb::new(&ray_in.direction).to_world(&local);
+
+        let scattered = Ray::new(hit_record.point, direction, ray_in.time);
+        let attenuation = self.albedo.value(hit_record.u, hit_record.v, &hit_record.point);
+        Some((scattered, attenuation))
+    }
+
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Color {
+        let albedo = self.albedo.value(hit_record.u, hit_record.v, &hit_record.point);
+        albedo * self.pdf(ray_in, hit_record, direction)
+    }
+
+    fn pdf(&self, ray_in: &Ray, _hit_record: &HitRecord, direction: &Vec3) -> f64 {
+        self.phase(ray_in.direction.dot(&direction.normalize()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Point3;
+    use crate::hittable::HitRecord;
+    use rand::SeedableRng;
+
+    /// A ray straight down onto the origin of the xy plane, and its hit there.
+    fn normal_incidence(material: Arc<dyn Material>) -> (Ray, HitRecord) {
+        let ray = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let hit_record = HitRecord {
+            point: Point3::zeros(),
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            t: 1.0,
+            u: 0.0,
+            v: 0.0,
+            material,
+            front_face: true,
+            on_light: false,
+        };
+        (ray, hit_record)
+    }
+
+    /// The density returned with each cosine weighted direction integrates to one over
+    /// the sphere and is the one `Lambertian::pdf` reports for it.
+    #[test]
+    fn cosine_sampler_density() {
+        const SAMPLES: usize = 200_000;
+        let lambertian = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let (ray, hit_record) = normal_incidence(Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))));
+        let mut rng = SmallRng::seed_from_u64(7);
+
+        let total: f64 = (0..SAMPLES).map(|_| cosine_pdf(random_unit_vec3(&mut rng).z)).sum();
+        let integral = 4.0 * PI * total / SAMPLES as f64;
+        assert!((integral - 1.0).abs() < 0.01, "integral {}", integral);
+
+        for _ in 0..1000 {
+            // The hit's normal is +z, so local and world directions coincide
+            let (direction, pdf) = random_cosine_direction(&mut rng);
+            assert!(pdf > 0.0);
+            let expected = lambertian.pdf(&ray, &hit_record, &direction);
+            assert!((pdf - expected).abs() < 1e-12, "{} vs {}", pdf, expected);
+        }
+    }
+
+    /// Compares the closed form `pdf` against a histogram of `scatter` at normal
+    /// incidence, binned by the cosine between the scattered and mirror directions.
+    #[test]
+    fn metal_pdf_matches_scatter() {
+        const SAMPLES: usize = 400_000;
+        const BINS: usize = 8;
+        let fuzz = 0.5;
+        let metal = Metal::new(Color::new(1.0, 1.0, 1.0), fuzz);
+        let (ray, hit_record) = normal_incidence(Arc::new(Metal::new(Color::new(1.0, 1.0, 1.0), fuzz)));
+        let mirror = Vec3::new(0.0, 0.0, 1.0);
+        // Scattered directions stay within this cosine of the mirror direction
+        let cos_min = (1.0 - fuzz * fuzz).sqrt();
+        let bin_width = (1.0 - cos_min) / BINS as f64;
+
+        let mut rng = SmallRng::seed_from_u64(7);
+        let mut histogram = [0usize; BINS];
+        for _ in 0..SAMPLES {
+            let (scattered, _) = metal.scatter(&ray, &hit_record, &mut rng).expect("above the surface");
+            let cosine = scattered.direction.normalize().dot(&mirror);
+            let bin = (((cosine - cos_min) / bin_width) as usize).min(BINS - 1);
+            histogram[bin] += 1;
+        }
+
+        // Integrate the density over each ring of directions with the midpoint rule
+        const STEPS: usize = 10_000;
+        for (bin, &count) in histogram.iter().enumerate() {
+            let start = cos_min + bin as f64 * bin_width;
+            let step = bin_width / STEPS as f64;
+            let expected: f64 = (0..STEPS)
+                .map(|i| {
+                    let cosine = start + (i as f64 + 0.5) * step;
+                    let sine = (1.0 - cosine * cosine).sqrt();
+                    let direction = Vec3::new(sine, 0.0, cosine);
+                    2.0 * PI * metal.pdf(&ray, &hit_record, &direction) * step
+                })
+                .sum();
+            let observed = count as f64 / SAMPLES as f64;
+            assert!(
+                (observed - expected).abs() < 0.01,
+                "bin {}: observed {}, expected {}",
+                bin,
+                observed,
+                expected
+            );
+        }
+    }
 }
@@ -0,0 +1,126 @@
+use crate::aabb::AABB;
+use crate::bvh::{Bvh, BvhConfig};
+use crate::common::*;
+use crate::hittable::{HitRecord, Hittable, Interval};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::triangle;
+use std::sync::Arc;
+
+/// Vertex buffers shared by every triangle of a mesh.
+#[derive(Default)]
+pub struct MeshData {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vec3>,
+    /// Texture coordinates.
+    pub uvs: Vec<(f64, f64)>,
+}
+
+/// One triangle of a mesh, as indices into the vertex buffers.
+pub struct MeshFace {
+    pub vertices: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+    pub material: Arc<dyn Material>,
+}
+
+/// Triangle mesh; its triangles share the vertex buffers and are kept in an internal BVH.
+pub struct Mesh {
+    bvh: Bvh,
+}
+
+impl Mesh {
+    /// Fails if there are no faces or a face indexes past the end of a vertex buffer.
+    pub fn new(mut data: MeshData, faces: Vec<MeshFace>, bvh: BvhConfig) -> Result<Self, String> {
+        if faces.is_empty() {
+            return Err("mesh has no faces".to_string());
+        }
+        data.normals.iter_mut().for_each(|n| *n = n.normalize());
+        let data = Arc::new(data);
+
+        let triangles = faces
+            .into_iter()
+            .map(|face| {
+                let triangle = MeshTriangle::new(Arc::clone(&data), face)?;
+                Ok(Box::new(triangle) as Box<dyn Hittable + Send + Sync>)
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            bvh: Bvh::from_list(triangles, bvh),
+        })
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        self.bvh.hit(ray, interval)
+    }
+
+    fn get_aabb(&self) -> &AABB {
+        self.bvh.get_aabb()
+    }
+}
+
+struct MeshTriangle {
+    data: Arc<MeshData>,
+    face: MeshFace,
+    aabb: AABB,
+}
+
+impl MeshTriangle {
+    fn new(data: Arc<MeshData>, face: MeshFace) -> Result<Self, String> {
+        check_indices(&face.vertices, data.positions.len(), "vertex")?;
+        if let Some(normals) = &face.normals {
+            check_indices(normals, data.normals.len(), "normal")?;
+        }
+        if let Some(uvs) = &face.uvs {
+            check_indices(uvs, data.uvs.len(), "uv")?;
+        }
+        let vertices = face.vertices.map(|i| data.positions[i]);
+        let aabb = triangle::triangle_aabb(&vertices);
+        Ok(Self { data, face, aabb })
+    }
+
+    fn vertices(&self) -> [Point3; 3] {
+        self.face.vertices.map(|i| self.data.positions[i])
+    }
+}
+
+fn check_indices(indices: &[usize; 3], len: usize, what: &str) -> Result<(), String> {
+    match indices.iter().find(|&&i| i >= len) {
+        Some(i) => Err(format!("{} index {} out of range (have {})", what, i, len)),
+        None => Ok(()),
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        let vertices = self.vertices();
+        let (t, u, v) = triangle::intersect(&vertices, ray, interval)?;
+        let normals = self.face.normals.map(|n| n.map(|i| self.data.normals[i]));
+        // Interpolate texture coordinates, or fall back to the barycentric ones
+        let uv = match self.face.uvs {
+            Some(uvs) => {
+                let [a, b, c] = uvs.map(|i| self.data.uvs[i]);
+                (
+                    (1.0 - u - v) * a.0 + u * b.0 + v * c.0,
+                    (1.0 - u - v) * a.1 + u * b.1 + v * c.1,
+                )
+            }
+            None => (u, v),
+        };
+        Some(triangle::hit_record(
+            ray,
+            t,
+            (u, v),
+            uv,
+            &vertices,
+            normals.as_ref(),
+            &self.face.material,
+        ))
+    }
+
+    fn get_aabb(&self) -> &AABB {
+        &self.aabb
+    }
+}
@@ -0,0 +1,283 @@
+use crate::bvh::BvhConfig;
+use crate::common::*;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::mesh::{Mesh, MeshData, MeshFace};
+use crate::scene::{parse_number, parse_vec3};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(PathBuf, std::io::Error),
+    Parse {
+        file: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(file, e) => write!(f, "{}: {}", file.display(), e),
+            ObjError::Parse { file, line, message } => {
+                write!(f, "{}:{}: {}", file.display(), line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+/// Load a Wavefront OBJ file as a single mesh. Materials come from the `mtllib` files it
+/// references; faces without `usemtl` use `default_material`.
+pub fn load_obj<P: AsRef<Path>>(
+    path: P,
+    default_material: Arc<dyn Material>,
+    bvh: BvhConfig,
+) -> Result<Mesh, ObjError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(|e| ObjError::Io(path.to_path_buf(), e))?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+
+    let mut data = MeshData::default();
+    let mut faces: Vec<MeshFace> = Vec::new();
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut current = default_material;
+
+    for (index, raw) in source.lines().enumerate() {
+        let fail = |message: String| ObjError::Parse {
+            file: path.to_path_buf(),
+            line: index + 1,
+            message,
+        };
+        let line = raw.split('#').next().unwrap_or("");
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&keyword, args)) = tokens.split_first() else {
+            continue;
+        };
+
+        match keyword {
+            "v" => {
+                // An optional fourth (w) component is ignored
+                if args.len() < 3 {
+                    return Err(fail(format!("v: expected 3 coordinates, got {}", args.len())));
+                }
+                data.positions.push(parse_vec3(args).map_err(fail)?);
+            }
+            "vn" => {
+                if args.len() < 3 {
+                    return Err(fail(format!("vn: expected 3 coordinates, got {}", args.len())));
+                }
+                let normal = parse_vec3(args).map_err(fail)?;
+                if near_zero(&normal) {
+                    return Err(fail("vn: normal must be non-zero".to_string()));
+                }
+                data.normals.push(normal);
+            }
+            "vt" => {
+                if args.is_empty() {
+                    return Err(fail("vt: expected texture coordinates".to_string()));
+                }
+                let u = parse_number(args[0]).map_err(fail)?;
+                let v = match args.get(1) {
+                    Some(v) => parse_number(v).map_err(fail)?,
+                    None => 0.0,
+                };
+                data.uvs.push((u, v));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(fail(format!("f: expected at least 3 vertices, got {}", args.len())));
+                }
+                let corners = args
+                    .iter()
+                    .map(|corner| parse_corner(corner, &data))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(fail)?;
+
+                let with_uvs = corners.iter().all(|c| c.1.is_some());
+                let with_normals = corners.iter().all(|c| c.2.is_some());
+                // Triangulate polygons as a fan around the first corner
+                for k in 1..corners.len() - 1 {
+                    let triangle = [corners[0], corners[k], corners[k + 1]];
+                    faces.push(MeshFace {
+                        vertices: triangle.map(|c| c.0),
+                        uvs: with_uvs.then(|| triangle.map(|c| c.1.unwrap())),
+                        normals: with_normals.then(|| triangle.map(|c| c.2.unwrap())),
+                        material: Arc::clone(&current),
+                    });
+                }
+            }
+            "mtllib" => {
+                for file in args {
+                    materials.extend(load_mtl(&base_dir.join(file))?);
+                }
+            }
+            "usemtl" => {
+                let name = args.join(" ");
+                current = materials
+                    .get(&name)
+                    .map(Arc::clone)
+                    .ok_or_else(|| fail(format!("undefined material '{}'", name)))?;
+            }
+            // Groups, objects, smoothing groups, lines, ...
+            _ => {}
+        }
+    }
+
+    if faces.is_empty() {
+        return Err(ObjError::Parse {
+            file: path.to_path_buf(),
+            line: source.lines().count(),
+            message: "file contains no faces".to_string(),
+        });
+    }
+    Mesh::new(data, faces, bvh).map_err(|message| ObjError::Parse {
+        file: path.to_path_buf(),
+        line: source.lines().count(),
+        message,
+    })
+}
+
+/// Properties of one `newmtl` entry, mapped onto our materials once complete.
+struct MtlEntry {
+    diffuse: Color,
+    specular: Color,
+    emission: Color,
+    shininess: f64,
+    refraction_index: f64,
+    dissolve: f64,
+    illum: u32,
+}
+
+impl Default for MtlEntry {
+    fn default() -> Self {
+        Self {
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::new(0.0, 0.0, 0.0),
+            emission: Color::new(0.0, 0.0, 0.0),
+            shininess: 0.0,
+            refraction_index: 1.5,
+            dissolve: 1.0,
+            illum: 2,
+        }
+    }
+}
+
+impl MtlEntry {
+    fn to_material(&self) -> Arc<dyn Material> {
+        let transparent = self.dissolve < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9);
+        let mirror = matches!(self.illum, 3 | 5)
+            || (self.diffuse.max() <= 0.0 && self.specular.max() > 0.0);
+
+        if self.emission.max() > 0.0 {
+            Arc::new(DiffuseLight::new(self.emission))
+        } else if transparent {
+            Arc::new(Dielectric::new(self.refraction_index))
+        } else if mirror {
+            // Map the Phong exponent to a roughness-like fuzz
+            let fuzz = (2.0 / (self.shininess + 2.0)).sqrt();
+            Arc::new(Metal::new(self.specular, fuzz))
+        } else {
+            Arc::new(Lambertian::new(self.diffuse))
+        }
+    }
+}
+
+fn load_mtl(path: &Path) -> Result<HashMap<String, Arc<dyn Material>>, ObjError> {
+    let source = std::fs::read_to_string(path).map_err(|e| ObjError::Io(path.to_path_buf(), e))?;
+
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlEntry)> = None;
+
+    for (index, raw) in source.lines().enumerate() {
+        let fail = |message: String| ObjError::Parse {
+            file: path.to_path_buf(),
+            line: index + 1,
+            message,
+        };
+        let line = raw.split('#').next().unwrap_or("");
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&keyword, args)) = tokens.split_first() else {
+            continue;
+        };
+
+        if keyword == "newmtl" {
+            if let Some((name, entry)) = current.take() {
+                materials.insert(name, entry.to_material());
+            }
+            current = Some((args.join(" "), MtlEntry::default()));
+            continue;
+        }
+        let Some((_, entry)) = current.as_mut() else {
+            return Err(fail(format!("'{}' before any newmtl", keyword)));
+        };
+
+        let color = |args: &[&str]| {
+            if args.len() < 3 {
+                return Err(fail(format!("{}: expected an RGB color", keyword)));
+            }
+            parse_vec3(args).map_err(fail)
+        };
+        let scalar = |args: &[&str]| match args.first() {
+            Some(value) => parse_number(value).map_err(fail),
+            None => Err(fail(format!("{}: expected a value", keyword))),
+        };
+
+        match keyword {
+            "Kd" => entry.diffuse = color(args)?,
+            "Ks" => entry.specular = color(args)?,
+            "Ke" => entry.emission = color(args)?,
+            "Ns" => entry.shininess = scalar(args)?,
+            "Ni" => entry.refraction_index = scalar(args)?,
+            "d" => entry.dissolve = scalar(args)?,
+            "Tr" => entry.dissolve = 1.0 - scalar(args)?,
+            "illum" => entry.illum = scalar(args)? as u32,
+            // Texture maps, ambient color, ...
+            _ => {}
+        }
+    }
+
+    if let Some((name, entry)) = current {
+        materials.insert(name, entry.to_material());
+    }
+    Ok(materials)
+}
+
+/// Parse a face corner `v`, `v/vt`, `v//vn` or `v/vt/vn` into zero based indices.
+fn parse_corner(
+    corner: &str,
+    data: &MeshData,
+) -> Result<(usize, Option<usize>, Option<usize>), String> {
+    let mut parts = corner.split('/');
+    let vertex = parts.next().unwrap_or("");
+    let uv = parts.next().filter(|s| !s.is_empty());
+    let normal = parts.next().filter(|s| !s.is_empty());
+    if parts.next().is_some() {
+        return Err(format!("malformed face corner '{}'", corner));
+    }
+
+    let vertex = resolve_index(vertex, data.positions.len(), "vertex")?;
+    let uv = uv
+        .map(|i| resolve_index(i, data.uvs.len(), "texture coordinate"))
+        .transpose()?;
+    let normal = normal
+        .map(|i| resolve_index(i, data.normals.len(), "normal"))
+        .transpose()?;
+    Ok((vertex, uv, normal))
+}
+
+/// OBJ indices are one based; negative ones count back from the latest element.
+fn resolve_index(token: &str, count: usize, what: &str) -> Result<usize, String> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| format!("invalid {} index '{}'", what, token))?;
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("{} index {} out of range ({} defined)", what, index, count));
+    }
+    Ok(resolved as usize)
+}
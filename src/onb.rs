@@ -0,0 +1,28 @@
+use crate::common::*;
+
+/// Orthonormal basis whose `w` axis points along a given direction, for sampling
+/// directions in a local frame and carrying them to world space.
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn new(direction: &Vec3) -> Self {
+        let w = direction.normalize();
+        let helper = if w.x.abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let u = helper.cross(&w).normalize();
+        let v = w.cross(&u);
+        Self { u, v, w }
+    }
+
+    /// World space vector with coordinates `local` in this basis.
+    pub fn to_world(&self, local: &Vec3) -> Vec3 {
+        local.x * self.u + local.y * self.v + local.z * self.w
+    }
+}
@@ -0,0 +1,106 @@
+use crate::common::*;
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise after Ken Perlin. The lattice is built from a seed, so the same seed
+/// gives the same pattern in every run.
+pub struct Perlin {
+    gradients: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let gradients = (0..POINT_COUNT).map(|_| random_unit_vec3(&mut rng)).collect();
+        let mut permutation = || {
+            let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+            p.shuffle(&mut rng);
+            p
+        };
+        let perm_x = permutation();
+        let perm_y = permutation();
+        let perm_z = permutation();
+        Self {
+            gradients,
+            perm_x,
+            perm_y,
+            perm_z,
+        }
+    }
+
+    /// Noise value in about [-1, 1], smooth in `point`.
+    pub fn noise(&self, point: &Point3) -> f64 {
+        let floor = point.map(f64::floor);
+        let fraction = point - floor;
+        let (i, j, k) = (floor.x as i64, floor.y as i64, floor.z as i64);
+        let wrap = |n: i64| (n & (POINT_COUNT as i64 - 1)) as usize;
+
+        let mut corners = [[[Vec3::zeros(); 2]; 2]; 2];
+        for (di, plane) in corners.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    let index = self.perm_x[wrap(i + di as i64)]
+                        ^ self.perm_y[wrap(j + dj as i64)]
+                        ^ self.perm_z[wrap(k + dk as i64)];
+                    *corner = self.gradients[index];
+                }
+            }
+        }
+        perlin_interpolation(&corners, &fraction)
+    }
+
+    /// Sum of `depth` octaves of absolute noise, each at double the frequency and half
+    /// the weight of the last; in [0, ~1].
+    pub fn turbulence(&self, point: &Point3, depth: usize) -> f64 {
+        let mut accumulated = 0.0;
+        let mut p = *point;
+        let mut weight = 1.0;
+        for _ in 0..depth {
+            accumulated += weight * self.noise(&p).abs();
+            weight *= 0.5;
+            p *= 2.0;
+        }
+        accumulated
+    }
+
+    /// Fractional Brownian motion: like `turbulence` but keeping the sign of each
+    /// octave, normalized back to about [-1, 1].
+    pub fn fbm(&self, point: &Point3, octaves: usize) -> f64 {
+        let mut accumulated = 0.0;
+        let mut total_weight = 0.0;
+        let mut p = *point;
+        let mut weight = 1.0;
+        for _ in 0..octaves {
+            accumulated += weight * self.noise(&p);
+            total_weight += weight;
+            weight *= 0.5;
+            p *= 2.0;
+        }
+        if total_weight > 0.0 { accumulated / total_weight } else { 0.0 }
+    }
+}
+
+/// Trilinear blend of the corner gradients with a Hermite smoothed weight.
+fn perlin_interpolation(corners: &[[[Vec3; 2]; 2]; 2], fraction: &Vec3) -> f64 {
+    let smooth = fraction.map(|t| t * t * (3.0 - 2.0 * t));
+    let mut accumulated = 0.0;
+    for (i, plane) in corners.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, gradient) in row.iter().enumerate() {
+                let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+                let weight = Vec3::new(fraction.x - fi, fraction.y - fj, fraction.z - fk);
+                accumulated += (fi * smooth.x + (1.0 - fi) * (1.0 - smooth.x))
+                    * (fj * smooth.y + (1.0 - fj) * (1.0 - smooth.y))
+                    * (fk * smooth.z + (1.0 - fk) * (1.0 - smooth.z))
+                    * gradient.dot(&weight);
+            }
+        }
+    }
+    accumulated
+}
@@ -0,0 +1,124 @@
+use crate::aabb::AABB;
+use crate::common::*;
+use crate::hittable::{HitRecord, Hittable, Interval};
+use crate::material::Material;
+use crate::ray::Ray;
+use rand::rngs::SmallRng;
+use std::sync::Arc;
+
+/// Parallelogram spanned by the edges `u` and `v` from the corner `q`.
+pub struct Quad {
+    pub q: Point3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub material: Arc<dyn Material>,
+    normal: Vec3,
+    d: f64,
+    w: Vec3,
+    area: f64,
+    aabb: AABB,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Self {
+        let n = u.cross(&v);
+        let normal = n.normalize();
+        let d = normal.dot(&q);
+        let w = n / n.dot(&n);
+        let area = n.norm();
+
+        // Bounding box of all four vertices
+        let diagonal_1 = AABB::from_point(&q, &(q + u + v));
+        let diagonal_2 = AABB::from_point(&(q + u), &(q + v));
+        let aabb = AABB::merge(&diagonal_1, &diagonal_2).pad_to_minimums();
+
+        Self {
+            q,
+            u,
+            v,
+            material,
+            normal,
+            d,
+            w,
+            area,
+            aabb,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        let denominator = self.normal.dot(&ray.direction);
+        // Ray parallel to the plane
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(&ray.origin)) / denominator;
+        if t < interval.start || t > interval.end {
+            return None;
+        }
+
+        // Express the hit point in the (u, v) frame of the quad
+        let point = ray.at(t);
+        let planar = point - self.q;
+        let alpha = self.w.dot(&planar.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let front_face = ray.direction.dot(&self.normal) < 0.0;
+        let normal = if front_face { self.normal } else { -self.normal };
+        Some(HitRecord {
+            point,
+            normal,
+            t,
+            u: alpha,
+            v: beta,
+            material: Arc::clone(&self.material),
+            front_face,
+            on_light: false,
+        })
+    }
+
+    fn get_aabb(&self) -> &AABB {
+        &self.aabb
+    }
+
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let ray = Ray::new(*origin, *direction, 0.0);
+        let Some(record) = self.hit(&ray, &Interval::new(0.001, f64::INFINITY)) else {
+            return 0.0;
+        };
+        // Convert the uniform density over the area to one over solid angle
+        let cosine = ray.direction.dot(&self.normal).abs();
+        record.t * record.t / (cosine * self.area)
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut SmallRng) -> Vec3 {
+        let point = self.q + random(rng) * self.u + random(rng) * self.v;
+        point - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::check_light_pdf;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn pdf_integrates_to_one() {
+        let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let quad = Quad::new(
+            Point3::new(-1.0, -0.5, -1.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.5, 1.0, -0.5),
+            material,
+        );
+        let (integral, covered) = check_light_pdf(&quad, &Point3::new(0.2, 0.1, 0.5), 200_000);
+        assert!((integral - 1.0).abs() < 0.03, "integral {}", integral);
+        assert!(covered > 0.999, "covered {}", covered);
+    }
+}
@@ -1,18 +1,65 @@
 use crate::common::*;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vec3,
+    /// Moment within the shutter interval the ray was sent at.
+    pub time: f64,
+    /// Componentwise `1 / direction`, infinite along axes the ray is parallel to.
+    pub inv_direction: Vec3,
+    /// 1 where the direction is negative, else 0: which end of a slab the ray meets first.
+    pub sign: [usize; 3],
 }
 
 impl Ray {
-    pub fn new(origin: Point3, direction: Vec3) -> Self {
+    pub fn new(origin: Point3, direction: Vec3, time: f64) -> Self {
         let direction = direction.normalize();
-        Self { origin, direction }
+        let inv_direction = direction.map(|d| 1.0 / d);
+        let sign = [0, 1, 2].map(|axis| inv_direction[axis].is_sign_negative() as usize);
+        Self {
+            origin,
+            direction,
+            time,
+            inv_direction,
+            sign,
+        }
     }
 
     pub fn at(self, t: f64) -> Point3 {
         self.origin + self.direction * t
     }
+
+    /// Hash of the ray mixed with the querying object's `salt`, for objects that need
+    /// random numbers inside `Hittable::hit`. The same ray always gives the same seed, so
+    /// repeated queries agree and seeded renders stay reproducible; the salt keeps
+    /// objects the ray passes through at once (e.g. overlapping media) from drawing the
+    /// same numbers. The draws are still not independent of the camera sampler, as they
+    /// are a function of the ray rather than of the pixel's random stream.
+    pub fn seed(&self, salt: u64) -> u64 {
+        let values = [
+            self.origin.x,
+            self.origin.y,
+            self.origin.z,
+            self.direction.x,
+            self.direction.y,
+            self.direction.z,
+            self.time,
+        ];
+        values.iter().fold(0x9E37_79B9_7F4A_7C15 ^ salt, |hash, value| {
+            // SplitMix64 finalizer over the running hash
+            let mut z = (hash ^ value.to_bits()).wrapping_add(0x9E37_79B9_7F4A_7C15);
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        })
+    }
+}
+
+/// A fresh salt for `Ray::seed`. Objects take one when they are created, so salts only
+/// depend on the order the scene builds them in.
+pub fn next_salt() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
 }
@@ -0,0 +1,967 @@
+use crate::background::{Background, EnvironmentMap, GradientBackground, SolidBackground};
+use crate::camera::CameraBuilder;
+use crate::common::*;
+use crate::hittable::Hittable;
+use crate::hittable_list::HittableList;
+use crate::integrator::IntegratorConfig;
+use crate::light::{Light, LightList};
+use crate::material::{
+    Dielectric, DiffuseLight, HenyeyGreenstein, Isotropic, Lambertian, Material, Metal,
+};
+use crate::bvh::{Bvh, BvhConfig};
+use crate::constant_medium::ConstantMedium;
+use crate::cuboid::Cuboid;
+use crate::disk::Disk;
+use crate::mesh::{Mesh, MeshData, MeshFace};
+use crate::obj::load_obj;
+use crate::quad::Quad;
+use crate::sphere::Sphere;
+use crate::texture::{
+    CheckerTexture, ImageTexture, MarbleTexture, NoiseTexture, SolidColor, Texture, UvCheckerTexture,
+};
+use crate::tlas::{Instance, Tlas};
+use crate::transform::Transformed;
+use crate::triangle::Triangle;
+use crate::volume::{DensityField, GridDensity, HeterogeneousMedium, NoiseDensity};
+use nalgebra::{Matrix4, Unit};
+use rand::rngs::SmallRng;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A parsed scene: camera and integrator parameters plus every object in world space.
+pub struct Scene {
+    pub camera: CameraBuilder,
+    pub integrator: IntegratorConfig,
+    pub world: HittableList,
+    /// Emitters of `world` that can be sampled directly.
+    pub lights: LightList,
+    /// Instances placed straight in the world. Kept out of `world` so their top level can
+    /// still be rebuilt; they join it for rendering.
+    pub instances: Option<Tlas>,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+    Empty,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "{}", e),
+            SceneError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            SceneError::Empty => write!(f, "scene contains no objects"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<std::io::Error> for SceneError {
+    fn from(e: std::io::Error) -> Self {
+        SceneError::Io(e)
+    }
+}
+
+impl Scene {
+    /// Load a scene file; `bvh` configures the BVHs built for meshes and objects.
+    pub fn load<P: AsRef<Path>>(path: P, bvh: BvhConfig) -> Result<Scene, SceneError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        // Resolve files referenced by the scene relative to the scene itself
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        Scene::parse(&source, base_dir, bvh)
+    }
+
+    /// Parse the line based scene format:
+    ///
+    /// ```text
+    /// # comment
+    /// camera look_from 13 2 3
+    /// texture white solid 0.9 0.9 0.9
+    /// texture black solid 0.1 0.1 0.1
+    /// texture board checker 0.5 white black
+    /// texture stone marble 4 42  0.2 0.2 0.25  0.9 0.9 0.9    # scale seed vein base
+    /// texture clouds noise 2 6 7  1 1 1                       # scale octaves seed tint
+    /// material ground lambertian texture board
+    /// material smoke henyey_greenstein 0.6  0.8 0.8 0.8         # g albedo
+    /// sphere 0 -1000 0 1000 ground
+    /// moving_sphere 0 1 0  0 1.5 0  0.5 ground
+    /// triangle 0 0 0  1 0 0  0 1 0 ground
+    /// quad -1 0 -1  2 0 0  0 0 2 ground
+    /// disk 0 2 0  0 -1 0  0.5 ground
+    /// box 0 0 0  1 1 1 ground
+    ///
+    /// obj models/teapot.obj ground
+    ///
+    /// object pyramid
+    /// obj models/pyramid.obj
+    /// end
+    /// instance pyramid rotate 0 1 0 45 translate 2 0 0
+    /// medium pyramid 0.5 smoke                # boundary density phase-function
+    /// volume pyramid noise 2 5 7 1.5 smoke    # scale octaves seed density phase-function
+    /// volume pyramid grid cloud.vol 2 smoke   # file density-scale phase-function
+    ///
+    /// mesh
+    /// vertex 0 0 0
+    /// vertex 1 0 0
+    /// vertex 0 1 0
+    /// face 0 1 2 ground
+    /// end
+    /// ```
+    pub fn parse(source: &str, base_dir: &Path, bvh: BvhConfig) -> Result<Scene, SceneError> {
+        let mut parser = Parser::new(base_dir, bvh);
+        for (index, raw) in source.lines().enumerate() {
+            let line = raw.split('#').next().unwrap_or("");
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.is_empty() {
+                continue;
+            }
+            parser.line = index + 1;
+            let result = if parser.mesh.is_some() {
+                parser.mesh_directive(&tokens)
+            } else {
+                parser.directive(&tokens)
+            };
+            result.map_err(|message| SceneError::Parse { line: parser.line, message })?;
+        }
+
+        if let Some(mesh) = &parser.mesh {
+            return Err(SceneError::Parse {
+                line: mesh.line,
+                message: "mesh is missing its 'end'".to_string(),
+            });
+        }
+        if let Some((name, line, _)) = &parser.object {
+            return Err(SceneError::Parse {
+                line: *line,
+                message: format!("object '{}' is missing its 'end'", name),
+            });
+        }
+
+        let instances = (!parser.instances.is_empty()).then(|| {
+            let instances = std::mem::take(&mut parser.instances);
+            Tlas::new(instances, parser.bvh)
+        });
+        if parser.world.objects.is_empty() && instances.is_none() {
+            return Err(SceneError::Empty);
+        }
+        Ok(Scene {
+            camera: parser.camera,
+            integrator: parser.integrator,
+            world: parser.world,
+            lights: parser.lights,
+            instances,
+        })
+    }
+
+    /// The final scene of _Ray Tracing in One Weekend_: a random field of small spheres
+    /// around three big ones.
+    pub fn book_cover(rng: &mut SmallRng) -> Scene {
+        let mut world = HittableList::new();
+
+        let material_ground: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        world.add(Box::new(Sphere::new(
+            Point3::new(0.0, -1000.0, 0.0),
+            1000.0,
+            Arc::clone(&material_ground),
+        )));
+
+        for a in -11..11 {
+            for b in -11..11 {
+                let choose_mat = random(rng);
+                let center = Point3::new(
+                    a as f64 + 0.9 * random(rng),
+                    0.2,
+                    b as f64 + 0.9 * random(rng),
+                );
+
+                if (center - Point3::new(4.0, 0.2, 0.0)).norm() > 0.9 {
+                    let material: Arc<dyn Material>;
+
+                    if choose_mat < 0.8 {
+                        // diffuse
+                        let albedo = random_vec3(rng).component_mul(&random_vec3(rng));
+                        material = Arc::new(Lambertian::new(albedo));
+                        world.add(Box::new(Sphere::new(center, 0.2, material)));
+                    } else if choose_mat < 0.95 {
+                        // metal
+                        let albedo = random_range_vec3(0.5, 1.0, rng);
+                        let fuzz = random_range(0.0, 0.5, rng);
+                        material = Arc::new(Metal::new(albedo, fuzz));
+                        world.add(Box::new(Sphere::new(center, 0.2, material)));
+                    } else {
+                        // glass
+                        material = Arc::new(Dielectric::new(1.5));
+                        world.add(Box::new(Sphere::new(center, 0.2, material)));
+                    }
+                }
+            }
+        }
+
+        let material_1: Arc<dyn Material> = Arc::new(Dielectric::new(1.5));
+        world.add(Box::new(Sphere::new(
+            Point3::new(0.0, 1.0, 0.0),
+            1.0,
+            Arc::clone(&material_1),
+        )));
+
+        let material_2: Arc<dyn Material> = Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
+        world.add(Box::new(Sphere::new(
+            Point3::new(-4.0, 1.0, 0.0),
+            1.0,
+            Arc::clone(&material_2),
+        )));
+
+        let material_3: Arc<dyn Material> = Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
+        world.add(Box::new(Sphere::new(
+            Point3::new(4.0, 1.0, 0.0),
+            1.0,
+            Arc::clone(&material_3),
+        )));
+
+        let camera = CameraBuilder {
+            aspect_ratio: 16.0 / 9.0,
+            vertical_fov: 20.0,
+            look_from: Point3::new(13.0, 2.0, 3.0),
+            look_at: Point3::new(0.0, 0.0, 0.0),
+            up: Vec3::new(0.0, 1.0, 0.0),
+            defocus_angle: 0.6,
+            focus_distance: 10.0,
+            ..CameraBuilder::default()
+        };
+
+        Scene {
+            camera,
+            integrator: IntegratorConfig::default(),
+            world,
+            lights: LightList::new(),
+            instances: None,
+        }
+    }
+}
+
+/// Accumulates the state of a scene file while it is read line by line.
+/// Errors are plain messages; `Scene::parse` attaches the line number.
+struct Parser {
+    line: usize,
+    base_dir: PathBuf,
+    bvh: BvhConfig,
+    camera: CameraBuilder,
+    integrator: IntegratorConfig,
+    world: HittableList,
+    lights: LightList,
+    materials: HashMap<String, Arc<dyn Material>>,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    mesh: Option<MeshBlock>,
+    /// Named objects that can be placed several times with `instance`.
+    objects: HashMap<String, Arc<dyn Hittable + Send + Sync>>,
+    /// The `object` block being read: its name, first line and contents.
+    object: Option<(String, usize, HittableList)>,
+    /// Instances placed directly in the world, gathered into one `Tlas`.
+    instances: Vec<Instance>,
+}
+
+/// A `mesh` ... `end` block being read. Indices in `face` lines are zero based.
+struct MeshBlock {
+    line: usize,
+    data: MeshData,
+    faces: Vec<MeshFace>,
+}
+
+impl Parser {
+    fn new(base_dir: &Path, bvh: BvhConfig) -> Self {
+        Self {
+            line: 0,
+            base_dir: base_dir.to_path_buf(),
+            bvh,
+            camera: CameraBuilder::default(),
+            integrator: IntegratorConfig::default(),
+            world: HittableList::new(),
+            lights: LightList::new(),
+            materials: HashMap::new(),
+            textures: HashMap::new(),
+            mesh: None,
+            objects: HashMap::new(),
+            object: None,
+            instances: Vec::new(),
+        }
+    }
+
+    fn directive(&mut self, tokens: &[&str]) -> Result<(), String> {
+        let args = &tokens[1..];
+        match tokens[0] {
+            "camera" => self.camera_setting(args),
+            "material" => self.material(args),
+            "texture" => self.texture(args),
+            "background" => self.background(args),
+            "sphere" => {
+                expect_args("sphere", args, 5)?;
+                let center = parse_vec3(&args[0..3])?;
+                let radius = parse_checked("sphere radius", args[3], "positive", |r| r > 0.0)?;
+                let material = self.lookup_material(args[4])?;
+                let emissive = material.is_emissive();
+                self.add_emitter(Sphere::new(center, radius, material), emissive);
+                Ok(())
+            }
+            "triangle" => {
+                let (vertices, normals, material) = match args.len() {
+                    10 => (&args[0..9], None, args[9]),
+                    19 => (&args[0..9], Some(&args[9..18]), args[18]),
+                    n => {
+                        return Err(format!(
+                            "triangle: expected 3 vertices, optionally 3 normals, and a material, got {} arguments",
+                            n
+                        ));
+                    }
+                };
+                let vertices = [
+                    parse_vec3(&vertices[0..3])?,
+                    parse_vec3(&vertices[3..6])?,
+                    parse_vec3(&vertices[6..9])?,
+                ];
+                let material = self.lookup_material(material)?;
+                let triangle = match normals {
+                    Some(n) => {
+                        let normals =
+                            [parse_vec3(&n[0..3])?, parse_vec3(&n[3..6])?, parse_vec3(&n[6..9])?];
+                        if normals.iter().any(near_zero) {
+                            return Err("triangle: normals must be non-zero".to_string());
+                        }
+                        Triangle::with_normals(vertices, normals, material)
+                    }
+                    None => Triangle::new(vertices, material),
+                };
+                self.add(Box::new(triangle));
+                Ok(())
+            }
+            "moving_sphere" => {
+                expect_args("moving_sphere", args, 8)?;
+                let center_0 = parse_vec3(&args[0..3])?;
+                let center_1 = parse_vec3(&args[3..6])?;
+                let radius = parse_checked("sphere radius", args[6], "positive", |r| r > 0.0)?;
+                let material = self.lookup_material(args[7])?;
+                self.add(Box::new(Sphere::moving(center_0, center_1, radius, material)));
+                Ok(())
+            }
+            "quad" => {
+                expect_args("quad", args, 10)?;
+                let q = parse_vec3(&args[0..3])?;
+                let u = parse_vec3(&args[3..6])?;
+                let v = parse_vec3(&args[6..9])?;
+                if near_zero(&u.cross(&v)) {
+                    return Err("quad: edges must not be parallel".to_string());
+                }
+                let material = self.lookup_material(args[9])?;
+                let emissive = material.is_emissive();
+                self.add_emitter(Quad::new(q, u, v, material), emissive);
+                Ok(())
+            }
+            "disk" => {
+                expect_args("disk", args, 8)?;
+                let center = parse_vec3(&args[0..3])?;
+                let normal = parse_vec3(&args[3..6])?;
+                let radius = parse_checked("disk radius", args[6], "positive", |r| r > 0.0)?;
+                if near_zero(&normal) {
+                    return Err("disk: normal must be non-zero".to_string());
+                }
+                let material = self.lookup_material(args[7])?;
+                self.add(Box::new(Disk::new(center, normal, radius, material)));
+                Ok(())
+            }
+            "box" => {
+                expect_args("box", args, 7)?;
+                let a = parse_vec3(&args[0..3])?;
+                let b = parse_vec3(&args[3..6])?;
+                // Each side is a quad spanned by two edges of the box, which must not be flat
+                if (b - a).iter().any(|side| side.abs() < 1e-8) {
+                    return Err("box: corners must differ along every axis".to_string());
+                }
+                let material = self.lookup_material(args[6])?;
+                self.add(Box::new(Cuboid::new(&a, &b, material)));
+                Ok(())
+            }
+            "obj" => {
+                let (file, material) = match args {
+                    [file] => (file, Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))) as Arc<dyn Material>),
+                    [file, material] => (file, self.lookup_material(material)?),
+                    _ => return Err("obj: expected '<file> [material]'".to_string()),
+                };
+                let mesh = load_obj(self.base_dir.join(file), material, self.bvh).map_err(|e| e.to_string())?;
+                self.add(Box::new(mesh));
+                Ok(())
+            }
+            "object" => {
+                expect_args("object", args, 1)?;
+                if self.object.is_some() {
+                    return Err("object blocks cannot be nested".to_string());
+                }
+                if self.objects.contains_key(args[0]) {
+                    return Err(format!("object '{}' is already defined", args[0]));
+                }
+                self.object = Some((args[0].to_string(), self.line, HittableList::new()));
+                Ok(())
+            }
+            "end" => {
+                expect_args("end", args, 0)?;
+                let Some((name, _, list)) = self.object.take() else {
+                    return Err("'end' without an open object or mesh".to_string());
+                };
+                if list.objects.is_empty() {
+                    return Err(format!("object '{}' is empty", name));
+                }
+                let object: Arc<dyn Hittable + Send + Sync> = Arc::new(Bvh::from_list(list.objects, self.bvh));
+                self.objects.insert(name, object);
+                Ok(())
+            }
+            "instance" => self.instance(args),
+            "volume" => self.volume(args),
+            "medium" => {
+                expect_args("medium", args, 3)?;
+                let boundary = self.lookup_object(args[0])?;
+                let density = parse_checked("medium density", args[1], "positive", |d| d > 0.0)?;
+                let phase_function = self.lookup_material(args[2])?;
+                self.add(Box::new(ConstantMedium::new(boundary, density, phase_function)));
+                Ok(())
+            }
+            "mesh" => {
+                expect_args("mesh", args, 0)?;
+                self.mesh = Some(MeshBlock {
+                    line: self.line,
+                    data: MeshData::default(),
+                    faces: Vec::new(),
+                });
+                Ok(())
+            }
+            other => Err(format!("unknown directive '{}'", other)),
+        }
+    }
+
+    /// Objects go to the open `object` block, if any, else straight into the world.
+    fn add(&mut self, object: Box<dyn Hittable + Send + Sync>) {
+        match &mut self.object {
+            Some((_, _, list)) => list.add(object),
+            None => self.world.add(object),
+        }
+    }
+
+    /// Like `add`, but emissive objects placed straight into the world are also sampled
+    /// as lights. Those in an `object` block are not, since instances may move them.
+    fn add_emitter<H: Hittable + Send + Sync + 'static>(&mut self, object: H, emissive: bool) {
+        if emissive && self.object.is_none() {
+            let light = Arc::new(Light::new(object));
+            self.lights.add(Arc::clone(&light) as Arc<dyn Hittable + Send + Sync>);
+            self.world.add(Box::new(light));
+        } else {
+            self.add(Box::new(object));
+        }
+    }
+
+    /// `volume <object> grid <file> <density-scale> <material>` or
+    /// `volume <object> noise <scale> <octaves> <seed> <density> <material>`,
+    /// a medium of varying density inside the object. A grid spans the object's bounding box.
+    fn volume(&mut self, args: &[&str]) -> Result<(), String> {
+        let (boundary, field, material): (_, Arc<dyn DensityField>, _) = match args {
+            [name, "grid", file, scale, material] => {
+                let boundary = self.lookup_object(name)?;
+                let aabb = boundary.get_aabb();
+                let corner = |end: bool| {
+                    Point3::from_fn(|axis, _| {
+                        let interval = aabb.axis_interval(axis);
+                        if end { interval.end } else { interval.start }
+                    })
+                };
+                let path = self.base_dir.join(file);
+                let scale = parse_checked("voxel density scale", scale, "non-negative", |s| s >= 0.0)?;
+                let grid = GridDensity::load(&path, corner(false), corner(true), scale)
+                    .map_err(|e| format!("cannot load voxel grid {}: {}", path.display(), e))?;
+                (boundary, Arc::new(grid), material)
+            }
+            [name, "noise", scale, octaves, seed, density, material] => {
+                let noise = NoiseDensity::new(
+                    parse_number(seed)?,
+                    parse_checked("noise scale", scale, "finite", |_| true)?,
+                    parse_number(octaves)?,
+                    parse_checked("noise density", density, "non-negative", |d| d >= 0.0)?,
+                );
+                (self.lookup_object(name)?, Arc::new(noise), material)
+            }
+            _ => {
+                return Err(
+                    "volume: expected '<object> grid <file> <density-scale> <material>' or '<object> noise <scale> <octaves> <seed> <density> <material>'"
+                        .to_string(),
+                );
+            }
+        };
+        let phase_function = self.lookup_material(material)?;
+        self.add(Box::new(HeterogeneousMedium::new(boundary, field, phase_function)));
+        Ok(())
+    }
+
+    /// `instance <object> [translate x y z] [rotate ax ay az degrees] [scale x y z] ...`,
+    /// the transforms apply in the order given.
+    fn instance(&mut self, args: &[&str]) -> Result<(), String> {
+        let Some((&name, mut rest)) = args.split_first() else {
+            return Err("instance: expected an object name".to_string());
+        };
+        let object = self.lookup_object(name)?;
+
+        let mut matrix = Matrix4::identity();
+        while let Some((&op, values)) = rest.split_first() {
+            let (step, count) = match op {
+                "translate" if values.len() >= 3 => {
+                    (Matrix4::new_translation(&parse_vec3(&values[0..3])?), 3)
+                }
+                "rotate" if values.len() >= 4 => {
+                    let axis = parse_vec3(&values[0..3])?;
+                    if near_zero(&axis) {
+                        return Err("rotate: axis must be non-zero".to_string());
+                    }
+                    let degrees: f64 = parse_number(values[3])?;
+                    let axis = Unit::new_normalize(axis);
+                    (Matrix4::from_axis_angle(&axis, degrees * PI / 180.0), 4)
+                }
+                "scale" if values.len() >= 3 => {
+                    let factors = parse_vec3(&values[0..3])?;
+                    if factors.iter().any(|f| f.abs() < 1e-12) {
+                        return Err("scale: factors must be non-zero".to_string());
+                    }
+                    (Matrix4::new_nonuniform_scaling(&factors), 3)
+                }
+                "translate" | "rotate" | "scale" => {
+                    return Err(format!("instance: '{}' is missing values", op));
+                }
+                other => return Err(format!("instance: unknown transform '{}'", other)),
+            };
+            matrix = step * matrix;
+            rest = &values[count..];
+        }
+
+        match &mut self.object {
+            // Nested instances become part of the enclosing object's own BVH
+            Some((_, _, list)) => list.add(Box::new(Transformed::new(object, matrix))),
+            None => self.instances.push(Instance::new(object, matrix)),
+        }
+        Ok(())
+    }
+
+    fn mesh_directive(&mut self, tokens: &[&str]) -> Result<(), String> {
+        let args = &tokens[1..];
+        if tokens[0] == "end" {
+            expect_args("end", args, 0)?;
+            let mesh = self.mesh.take().expect("inside a mesh block");
+            self.add(Box::new(Mesh::new(mesh.data, mesh.faces, self.bvh)?));
+            return Ok(());
+        }
+
+        let material = match tokens[0] {
+            "face" => {
+                expect_args("face", args, 4)?;
+                Some(self.lookup_material(args[3])?)
+            }
+            _ => None,
+        };
+        let mesh = self.mesh.as_mut().expect("inside a mesh block");
+        match tokens[0] {
+            "vertex" => {
+                expect_args("vertex", args, 3)?;
+                mesh.data.positions.push(parse_vec3(args)?);
+            }
+            "normal" => {
+                expect_args("normal", args, 3)?;
+                let normal = parse_vec3(args)?;
+                if near_zero(&normal) {
+                    return Err("normal must be non-zero".to_string());
+                }
+                mesh.data.normals.push(normal);
+            }
+            "face" => {
+                // Each corner is `v` or `v/n`
+                let mut vertices = [0; 3];
+                let mut normals = [0; 3];
+                let mut with_normals = 0;
+                for (k, corner) in args[0..3].iter().enumerate() {
+                    let (v, n) = match corner.split_once('/') {
+                        Some((v, n)) => (v, Some(n)),
+                        None => (*corner, None),
+                    };
+                    vertices[k] = parse_index(v, mesh.data.positions.len(), "vertex")?;
+                    if let Some(n) = n {
+                        normals[k] = parse_index(n, mesh.data.normals.len(), "normal")?;
+                        with_normals += 1;
+                    }
+                }
+                let normals = match with_normals {
+                    0 => None,
+                    3 => Some(normals),
+                    _ => return Err("face: either all or no corners must have normals".to_string()),
+                };
+                mesh.faces.push(MeshFace {
+                    vertices,
+                    normals,
+                    uvs: None,
+                    material: material.expect("looked up above"),
+                });
+            }
+            other => return Err(format!("unknown mesh directive '{}'", other)),
+        }
+        Ok(())
+    }
+
+    fn camera_setting(&mut self, args: &[&str]) -> Result<(), String> {
+        let Some((&key, values)) = args.split_first() else {
+            return Err("camera: missing setting name".to_string());
+        };
+        let name = format!("camera {}", key);
+        match key {
+            "aspect_ratio" => self.camera.aspect_ratio = parse_single(&name, values)?,
+            "width" => self.camera.width = parse_single(&name, values)?,
+            "height" => self.camera.height = Some(parse_single(&name, values)?),
+            "samples" => self.camera.sample_per_pixel = parse_single(&name, values)?,
+            "max_depth" => self.integrator.max_depth = parse_single(&name, values)?,
+            "vfov" => self.camera.vertical_fov = parse_single(&name, values)?,
+            "defocus_angle" => self.camera.defocus_angle = parse_single(&name, values)?,
+            "focus_distance" => self.camera.focus_distance = parse_single(&name, values)?,
+            "shutter" => {
+                expect_args(&name, values, 2)?;
+                self.camera.shutter_open = parse_number(values[0])?;
+                self.camera.shutter_close = parse_number(values[1])?;
+            }
+            "look_from" => {
+                expect_args(&name, values, 3)?;
+                self.camera.look_from = parse_vec3(values)?;
+            }
+            "look_at" => {
+                expect_args(&name, values, 3)?;
+                self.camera.look_at = parse_vec3(values)?;
+            }
+            "up" => {
+                expect_args(&name, values, 3)?;
+                self.camera.up = parse_vec3(values)?;
+            }
+            other => return Err(format!("unknown camera setting '{}'", other)),
+        }
+        Ok(())
+    }
+
+    fn background(&mut self, args: &[&str]) -> Result<(), String> {
+        let background: Arc<dyn Background> = match args {
+            ["solid", values @ ..] => {
+                expect_args("background solid", values, 3)?;
+                Arc::new(SolidBackground::new(parse_vec3(values)?))
+            }
+            ["sky"] => Arc::new(GradientBackground::sky()),
+            ["gradient", values @ ..] => {
+                expect_args("background gradient", values, 6)?;
+                Arc::new(GradientBackground::new(
+                    parse_vec3(&values[0..3])?,
+                    parse_vec3(&values[3..6])?,
+                ))
+            }
+            ["envmap", file, rest @ ..] => {
+                let intensity = match rest {
+                    [] => 1.0,
+                    [value] => parse_checked("envmap intensity", value, "non-negative", |i| i >= 0.0)?,
+                    _ => return Err("background envmap: expected '<file> [intensity]'".to_string()),
+                };
+                let path = self.base_dir.join(file);
+                let map = EnvironmentMap::load(&path, intensity)
+                    .map_err(|e| format!("cannot load environment map {}: {}", path.display(), e))?;
+                Arc::new(map)
+            }
+            // `background r g b` is shorthand for a solid color
+            [_, _, _] => Arc::new(SolidBackground::new(parse_vec3(args)?)),
+            _ => {
+                return Err(
+                    "background: expected 'r g b', 'solid r g b', 'sky', 'gradient <bottom> <top>' or 'envmap <file> [intensity]'"
+                        .to_string(),
+                );
+            }
+        };
+        self.integrator.background = background;
+        Ok(())
+    }
+
+    fn material(&mut self, args: &[&str]) -> Result<(), String> {
+        if args.len() < 2 {
+            return Err("material: expected '<name> <kind> ...'".to_string());
+        }
+        let name = args[0];
+        let values = &args[2..];
+        let material: Arc<dyn Material> = match args[1] {
+            "lambertian" => {
+                let (albedo, rest) = self.albedo("lambertian", values)?;
+                expect_args("lambertian", rest, 0)?;
+                Arc::new(Lambertian::from_texture(albedo))
+            }
+            "metal" => {
+                let (albedo, rest) = self.albedo("metal", values)?;
+                expect_args("metal fuzz", rest, 1)?;
+                let fuzz = parse_checked("metal fuzz", rest[0], "in [0, 1]", |f| (0.0..=1.0).contains(&f))?;
+                Arc::new(Metal::from_texture(albedo, fuzz))
+            }
+            "dielectric" => {
+                expect_args("dielectric", values, 1)?;
+                let index = parse_checked("dielectric index", values[0], "positive", |n| n > 0.0)?;
+                Arc::new(Dielectric::new(index))
+            }
+            "isotropic" => {
+                let (albedo, rest) = self.albedo("isotropic", values)?;
+                expect_args("isotropic", rest, 0)?;
+                Arc::new(Isotropic::from_texture(albedo))
+            }
+            "henyey_greenstein" => {
+                let Some((g, values)) = values.split_first() else {
+                    return Err("henyey_greenstein: expected '<g> <albedo>'".to_string());
+                };
+                let g = parse_checked("henyey_greenstein g", g, "in (-1, 1)", |g| g > -1.0 && g < 1.0)?;
+                let (albedo, rest) = self.albedo("henyey_greenstein", values)?;
+                expect_args("henyey_greenstein", rest, 0)?;
+                Arc::new(HenyeyGreenstein::from_texture(albedo, g))
+            }
+            "diffuse_light" => {
+                let (emit, rest) = self.albedo("diffuse_light", values)?;
+                expect_args("diffuse_light", rest, 0)?;
+                Arc::new(DiffuseLight::from_texture(emit))
+            }
+            other => return Err(format!("unknown material kind '{}'", other)),
+        };
+        if self.materials.insert(name.to_string(), material).is_some() {
+            return Err(format!("material '{}' is already defined", name));
+        }
+        Ok(())
+    }
+
+    /// A color argument is either `r g b` or `texture <name>`; returns the remaining arguments.
+    fn albedo<'a>(&self, what: &str, values: &'a [&'a str]) -> Result<(Arc<dyn Texture>, &'a [&'a str]), String> {
+        match values {
+            ["texture", name, rest @ ..] => Ok((self.lookup_texture(name)?, rest)),
+            [_, _, _, rest @ ..] => Ok((Arc::new(SolidColor::new(parse_vec3(values)?)), rest)),
+            _ => Err(format!("{}: expected 'r g b' or 'texture <name>'", what)),
+        }
+    }
+
+    fn texture(&mut self, args: &[&str]) -> Result<(), String> {
+        if args.len() < 2 {
+            return Err("texture: expected '<name> <kind> ...'".to_string());
+        }
+        let name = args[0];
+        let values = &args[2..];
+        let texture: Arc<dyn Texture> = match args[1] {
+            "solid" => {
+                expect_args("solid", values, 3)?;
+                Arc::new(SolidColor::new(parse_vec3(values)?))
+            }
+            "checker" => {
+                expect_args("checker", values, 3)?;
+                let scale = parse_checked("checker scale", values[0], "positive", |s| s > 0.0)?;
+                let even = self.lookup_texture(values[1])?;
+                let odd = self.lookup_texture(values[2])?;
+                Arc::new(CheckerTexture::new(scale, even, odd))
+            }
+            "uv_checker" => {
+                expect_args("uv_checker", values, 4)?;
+                let columns = parse_checked("uv_checker columns", values[0], "positive", |c| c > 0.0)?;
+                let rows = parse_checked("uv_checker rows", values[1], "positive", |r| r > 0.0)?;
+                let even = self.lookup_texture(values[2])?;
+                let odd = self.lookup_texture(values[3])?;
+                Arc::new(UvCheckerTexture::new(columns, rows, even, odd))
+            }
+            "noise" => {
+                expect_args("noise", values, 6)?;
+                let scale = parse_checked("noise scale", values[0], "finite", |_| true)?;
+                let octaves = parse_number(values[1])?;
+                let seed = parse_number(values[2])?;
+                Arc::new(NoiseTexture::new(seed, scale, octaves, parse_vec3(&values[3..6])?))
+            }
+            "marble" => {
+                expect_args("marble", values, 8)?;
+                let scale = parse_checked("marble scale", values[0], "finite", |_| true)?;
+                let seed = parse_number(values[1])?;
+                let vein = parse_vec3(&values[2..5])?;
+                let base = parse_vec3(&values[5..8])?;
+                Arc::new(MarbleTexture::new(seed, scale, vein, base))
+            }
+            "image" => {
+                expect_args("image", values, 1)?;
+                let path = self.base_dir.join(values[0]);
+                let image = ImageTexture::load(&path)
+                    .map_err(|e| format!("cannot load texture {}: {}", path.display(), e))?;
+                Arc::new(image)
+            }
+            other => return Err(format!("unknown texture kind '{}'", other)),
+        };
+        if self.textures.insert(name.to_string(), texture).is_some() {
+            return Err(format!("texture '{}' is already defined", name));
+        }
+        Ok(())
+    }
+
+    fn lookup_texture(&self, name: &str) -> Result<Arc<dyn Texture>, String> {
+        self.textures
+            .get(name)
+            .map(Arc::clone)
+            .ok_or_else(|| format!("undefined texture '{}'", name))
+    }
+
+    fn lookup_object(&self, name: &str) -> Result<Arc<dyn Hittable + Send + Sync>, String> {
+        self.objects
+            .get(name)
+            .map(Arc::clone)
+            .ok_or_else(|| format!("undefined object '{}'", name))
+    }
+
+    fn lookup_material(&self, name: &str) -> Result<Arc<dyn Material>, String> {
+        self.materials
+            .get(name)
+            .map(Arc::clone)
+            .ok_or_else(|| format!("undefined material '{}'", name))
+    }
+}
+
+fn expect_args(what: &str, args: &[&str], count: usize) -> Result<(), String> {
+    if args.len() != count {
+        return Err(format!("{}: expected {} arguments, got {}", what, count, args.len()));
+    }
+    Ok(())
+}
+
+pub(crate) fn parse_number<T: FromStr>(token: &str) -> Result<T, String> {
+    token
+        .parse()
+        .map_err(|_| format!("invalid number '{}'", token))
+}
+
+/// Parse a finite number that also passes `valid`; `requirement` describes the accepted
+/// range in the error, e.g. "positive".
+fn parse_checked(what: &str, token: &str, requirement: &str, valid: impl Fn(f64) -> bool) -> Result<f64, String> {
+    let value: f64 = parse_number(token)?;
+    if !(value.is_finite() && valid(value)) {
+        return Err(format!("{} must be {}, got {}", what, requirement, value));
+    }
+    Ok(value)
+}
+
+fn parse_single<T: FromStr>(what: &str, args: &[&str]) -> Result<T, String> {
+    expect_args(what, args, 1)?;
+    parse_number(args[0])
+}
+
+fn parse_index(token: &str, count: usize, what: &str) -> Result<usize, String> {
+    let index: usize = parse_number(token)?;
+    if index >= count {
+        return Err(format!("{} index {} out of range ({} defined)", what, index, count));
+    }
+    Ok(index)
+}
+
+pub(crate) fn parse_vec3(args: &[&str]) -> Result<Vec3, String> {
+    let component = |token: &str| parse_checked("vector component", token, "finite", |_| true);
+    Ok(Vec3::new(component(args[0])?, component(args[1])?, component(args[2])?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Line and message of the error `source` fails to parse with.
+    fn parse_error(source: &str) -> (usize, String) {
+        let base_dir = std::env::temp_dir().join("rt-rs-scene-tests");
+        match Scene::parse(source, &base_dir, BvhConfig::default()) {
+            Err(SceneError::Parse { line, message }) => (line, message),
+            Err(e) => panic!("expected a parse error, got: {}", e),
+            Ok(_) => panic!("expected a parse error, but the scene parsed"),
+        }
+    }
+
+    const MATERIAL: &str = "material grey lambertian 0.5 0.5 0.5\n";
+
+    #[test]
+    fn unknown_directive() {
+        let (line, message) = parse_error(&format!("{}\n# comment\nteapot 1 2 3\n", MATERIAL));
+        assert_eq!(line, 4);
+        assert!(message.contains("unknown directive 'teapot'"), "{}", message);
+    }
+
+    #[test]
+    fn wrong_argument_count() {
+        let (line, message) = parse_error(&format!("{}sphere 0 0 0 1\n", MATERIAL));
+        assert_eq!(line, 2);
+        assert!(message.contains("expected 5 arguments, got 4"), "{}", message);
+
+        let (line, message) = parse_error("camera\ncamera vfov 20 30\n");
+        assert_eq!(line, 1);
+        assert!(message.contains("missing setting name"), "{}", message);
+        let (line, _) = parse_error("camera vfov 20\ncamera vfov 20 30\n");
+        assert_eq!(line, 2);
+    }
+
+    #[test]
+    fn undefined_material() {
+        let (line, message) = parse_error(&format!("{}sphere 0 0 0 1 gray\n", MATERIAL));
+        assert_eq!(line, 2);
+        assert!(message.contains("undefined material 'gray'"), "{}", message);
+    }
+
+    #[test]
+    fn bad_number() {
+        let (line, message) = parse_error(&format!("{}\nsphere 0 0 zero 1 grey\n", MATERIAL));
+        assert_eq!(line, 3);
+        assert!(message.contains("invalid number 'zero'"), "{}", message);
+
+        let (line, message) = parse_error(&format!("{}sphere 0 0 0 nan grey\n", MATERIAL));
+        assert_eq!(line, 2);
+        assert!(message.contains("sphere radius must be positive"), "{}", message);
+    }
+
+    #[test]
+    fn degenerate_shapes() {
+        let (line, message) = parse_error(&format!("{}box 0 0 0 1 0 1 grey\n", MATERIAL));
+        assert_eq!(line, 2);
+        assert!(message.contains("box: corners must differ"), "{}", message);
+    }
+
+    #[test]
+    fn obj_file_errors() {
+        let (line, message) = parse_error(&format!("{}\nobj missing.obj grey\n", MATERIAL));
+        assert_eq!(line, 3);
+        assert!(message.contains("missing.obj"), "{}", message);
+
+        // Errors inside the OBJ carry both the scene line and the file's own line
+        let base_dir = std::env::temp_dir().join("rt-rs-scene-tests");
+        std::fs::create_dir_all(&base_dir).unwrap();
+        std::fs::write(base_dir.join("broken.obj"), "v 0 0 0\nv 1 0 0\nv 0 one 0\nf 1 2 3\n").unwrap();
+        let (line, message) = parse_error(&format!("{}obj broken.obj grey\n", MATERIAL));
+        assert_eq!(line, 2);
+        assert!(message.contains("broken.obj:3: invalid number 'one'"), "{}", message);
+    }
+
+    #[test]
+    fn bad_mesh_block() {
+        let source = format!("{}mesh\nvertex 0 0 0\nvertex 1 0 0\nface 0 1 2 grey\nend\n", MATERIAL);
+        let (line, message) = parse_error(&source);
+        assert_eq!(line, 5);
+        assert!(message.contains("vertex index 2 out of range"), "{}", message);
+
+        // An unterminated block is reported where it starts
+        let (line, message) = parse_error(&format!("{}mesh\nvertex 0 0 0\n", MATERIAL));
+        assert_eq!(line, 2);
+        assert!(message.contains("missing its 'end'"), "{}", message);
+    }
+
+    #[test]
+    fn bad_object_block() {
+        let (line, message) = parse_error(&format!("{}object empty\nend\n", MATERIAL));
+        assert_eq!(line, 3);
+        assert!(message.contains("object 'empty' is empty"), "{}", message);
+
+        let (line, message) = parse_error(&format!("{}instance nothing\n", MATERIAL));
+        assert_eq!(line, 2);
+        assert!(message.contains("nothing"), "{}", message);
+    }
+}
@@ -1,12 +1,17 @@
 use crate::common::*;
 use crate::hittable::{HitRecord, Hittable, Interval};
 use crate::material::Material;
+use crate::onb::Onb;
 use crate::ray::Ray;
+use rand::rngs::SmallRng;
 use std::sync::Arc;
 use crate::aabb::AABB;
 
 pub struct Sphere {
+    /// Center at time 0.
     pub center: Point3,
+    /// Displacement of the center from time 0 to time 1, zero for static spheres.
+    pub motion: Vec3,
     pub radius: f64,
     pub material: Arc<dyn Material>,
     aabb: AABB,
@@ -19,11 +24,43 @@ impl Sphere {
         let aabb = AABB::from_point(&a, &b);
         Self {
             center,
+            motion: Vec3::zeros(),
             radius,
             material,
             aabb,
         }
     }
+
+    /// A sphere moving linearly from `center_0` at time 0 to `center_1` at time 1.
+    pub fn moving(center_0: Point3, center_1: Point3, radius: f64, material: Arc<dyn Material>) -> Self {
+        let mut sphere = Self::new(center_0, radius, material);
+        let end = Self::new(center_1, radius, Arc::clone(&sphere.material));
+        // The box must hold the sphere over the whole motion for the BVH to stay correct
+        sphere.aabb = AABB::merge(&sphere.aabb, &end.aabb);
+        sphere.motion = center_1 - center_0;
+        sphere
+    }
+
+    /// Map a point on the unit sphere to (u, v): longitude around y from -x, and
+    /// latitude from the south pole.
+    fn get_sphere_uv(p: &Point3) -> (f64, f64) {
+        let theta = (-p.y).clamp(-1.0, 1.0).acos();
+        let phi = (-p.z).atan2(p.x) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    /// Cosine of the half angle of the cone the sphere subtends from `origin`, or `None`
+    /// when `origin` is inside it and every direction leads to the sphere.
+    fn cos_theta_max(&self, origin: &Point3) -> Option<f64> {
+        let distance_squared = (self.center - origin).norm_squared();
+        let radius_squared = self.radius * self.radius;
+        (distance_squared > radius_squared).then(|| (1.0 - radius_squared / distance_squared).sqrt())
+    }
+
+    fn center_at(&self, time: f64) -> Point3 {
+        // Clamp so the sphere never leaves its bounding box
+        self.center + time.clamp(0.0, 1.0) * self.motion
+    }
 }
 
 impl Hittable for Sphere {
@@ -33,7 +70,8 @@ impl Hittable for Sphere {
         }
 
         // Solve |(ray.origin + t*ray.direction) - center|^2 = radius^2
-        let oc = ray.origin - self.center;
+        let center = self.center_at(ray.time);
+        let oc = ray.origin - center;
         let a = ray.direction.dot(&ray.direction);
         let half_b = oc.dot(&ray.direction); // since equation is a t^2 + 2*b t + c, use half_b = b
         let c = oc.dot(&oc) - self.radius * self.radius;
@@ -53,7 +91,7 @@ impl Hittable for Sphere {
         }
 
         let point = ray.at(root);
-        let outward_normal = (point - self.center) / self.radius;
+        let outward_normal = (point - center) / self.radius;
         // Determine front_face and adjust normal to always oppose the ray direction
         let front_face = ray.direction.dot(&outward_normal) < 0.0;
         let normal = if front_face {
@@ -62,17 +100,78 @@ impl Hittable for Sphere {
             -outward_normal
         };
         let normal = normal.normalize();
+        let (u, v) = Self::get_sphere_uv(&outward_normal);
 
         Some(HitRecord {
             point,
             normal,
             t: root,
+            u,
+            v,
             material: Arc::clone(&self.material),
             front_face,
+            on_light: false,
         })
     }
 
     fn get_aabb(&self) -> &AABB {
         &self.aabb
     }
+
+    /// Uniform over the cone of directions the sphere subtends, or over all directions
+    /// from inside it; either way this assumes it is not moving.
+    fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+        let ray = Ray::new(*origin, *direction, 0.0);
+        if self.hit(&ray, &Interval::new(0.001, f64::INFINITY)).is_none() {
+            return 0.0;
+        }
+        let Some(cos_theta_max) = self.cos_theta_max(origin) else {
+            return 1.0 / (4.0 * PI);
+        };
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
+
+    fn random(&self, origin: &Point3, rng: &mut SmallRng) -> Vec3 {
+        let Some(cos_theta_max) = self.cos_theta_max(origin) else {
+            return random_unit_vec3(rng);
+        };
+
+        // Uniform direction inside the cone around the axis to the center
+        let z = 1.0 + random(rng) * (cos_theta_max - 1.0);
+        let phi = 2.0 * PI * random(rng);
+        let sin_theta = (1.0 - z * z).sqrt();
+
+        let local = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), z);
+        Onb::new(&(self.center - origin)).to_world(&local)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::check_light_pdf;
+    use crate::material::Lambertian;
+
+    const SAMPLES: usize = 200_000;
+
+    fn sphere(center: Point3, radius: f64) -> Sphere {
+        Sphere::new(center, radius, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))))
+    }
+
+    #[test]
+    fn pdf_integrates_to_one_from_outside() {
+        let sphere = sphere(Point3::new(0.0, 0.0, -2.0), 1.0);
+        let (integral, covered) = check_light_pdf(&sphere, &Point3::zeros(), SAMPLES);
+        assert!((integral - 1.0).abs() < 0.03, "integral {}", integral);
+        assert!(covered > 0.999, "covered {}", covered);
+    }
+
+    #[test]
+    fn pdf_integrates_to_one_from_inside() {
+        let sphere = sphere(Point3::new(0.3, 0.0, 0.0), 1.0);
+        let (integral, covered) = check_light_pdf(&sphere, &Point3::zeros(), SAMPLES);
+        assert!((integral - 1.0).abs() < 1e-9, "integral {}", integral);
+        assert_eq!(covered, 1.0);
+    }
 }
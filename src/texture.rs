@@ -0,0 +1,176 @@
+use crate::common::*;
+use crate::interval::Interval;
+use crate::perlin::Perlin;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Spatially varying color, looked up by surface coordinates and/or world position.
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, point: &Point3) -> Color;
+}
+
+pub struct SolidColor {
+    pub albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _point: &Point3) -> Color {
+        self.albedo
+    }
+}
+
+/// 3D checker pattern of cubes with edge length `scale`, independent of the surface.
+pub struct CheckerTexture {
+    inv_scale: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl CheckerTexture {
+    pub fn new(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            inv_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, point: &Point3) -> Color {
+        let cell = point.map(|c| (self.inv_scale * c).floor() as i64);
+        if (cell.x + cell.y + cell.z).rem_euclid(2) == 0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
+
+/// Checker pattern with `columns` x `rows` cells over the UV square.
+pub struct UvCheckerTexture {
+    columns: f64,
+    rows: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl UvCheckerTexture {
+    pub fn new(columns: f64, rows: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            columns,
+            rows,
+            even,
+            odd,
+        }
+    }
+}
+
+impl Texture for UvCheckerTexture {
+    fn value(&self, u: f64, v: f64, point: &Point3) -> Color {
+        let i = (u * self.columns).floor() as i64;
+        let j = (v * self.rows).floor() as i64;
+        if (i + j).rem_euclid(2) == 0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
+
+/// Image mapped over the UV square, `v = 0` being the bottom row.
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl ImageTexture {
+    pub fn load<P: AsRef<Path>>(path: P) -> image::ImageResult<Self> {
+        let image = image::open(path)?.into_rgb8();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        // Undo the gamma of the stored image so shading happens in linear space
+        let pixels = image
+            .pixels()
+            .map(|p| Color::new(p[0] as f64, p[1] as f64, p[2] as f64) / 255.0)
+            .map(|c| c.component_mul(&c))
+            .collect();
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _point: &Point3) -> Color {
+        let unit = Interval::new(0.0, 1.0);
+        let u = unit.clamp(u);
+        let v = 1.0 - unit.clamp(v); // flip to image row order
+
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[j * self.width + i]
+    }
+}
+
+/// Cloudy grey scale pattern from fractional Brownian motion, tinted by `color`.
+pub struct NoiseTexture {
+    noise: Perlin,
+    scale: f64,
+    octaves: usize,
+    color: Color,
+}
+
+impl NoiseTexture {
+    pub fn new(seed: u64, scale: f64, octaves: usize, color: Color) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            octaves,
+            color,
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, point: &Point3) -> Color {
+        let n = self.noise.fbm(&(self.scale * point), self.octaves);
+        0.5 * (1.0 + n) * self.color
+    }
+}
+
+/// Marble veins: a sine along z whose phase is disturbed by turbulence.
+pub struct MarbleTexture {
+    noise: Perlin,
+    scale: f64,
+    vein: Color,
+    base: Color,
+}
+
+impl MarbleTexture {
+    pub fn new(seed: u64, scale: f64, vein: Color, base: Color) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            vein,
+            base,
+        }
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, _u: f64, _v: f64, point: &Point3) -> Color {
+        let point = self.scale * point;
+        let phase = point.z + 10.0 * self.noise.turbulence(&point, 7);
+        let t = 0.5 * (1.0 + phase.sin());
+        (1.0 - t) * self.vein + t * self.base
+    }
+}
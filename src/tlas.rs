@@ -0,0 +1,141 @@
+use crate::aabb::AABB;
+use crate::bvh::{Bvh, BvhConfig, BvhNode};
+use crate::hittable::{HitRecord, Hittable, Interval};
+use crate::ray::Ray;
+use crate::transform::Transformed;
+use nalgebra::Matrix4;
+use std::sync::Arc;
+
+/// One placement of shared geometry in the world.
+pub struct Instance {
+    /// The bottom-level structure, e.g. the `Bvh` of a mesh or an `object` block.
+    pub geometry: Arc<dyn Hittable + Send + Sync>,
+    transform: Matrix4<f64>,
+    inverse: Matrix4<f64>,
+}
+
+impl Instance {
+    pub fn new(geometry: Arc<dyn Hittable + Send + Sync>, transform: Matrix4<f64>) -> Self {
+        let mut instance = Self {
+            geometry,
+            transform: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        };
+        instance.set_transform(transform);
+        instance
+    }
+
+    pub fn transform(&self) -> &Matrix4<f64> {
+        &self.transform
+    }
+
+    /// Move the instance; the inverse is cached here so top-level rebuilds reuse it.
+    pub fn set_transform(&mut self, transform: Matrix4<f64>) {
+        self.inverse = transform
+            .try_inverse()
+            .expect("transform matrix must be invertible");
+        self.transform = transform;
+    }
+}
+
+/// Two-level acceleration structure: a top-level BVH over instances, each pointing to a
+/// bottom-level structure shared by all copies of that geometry. Memory grows with the
+/// unique geometry rather than the number of copies, and moving instances only needs the
+/// small top level rebuilt.
+pub struct Tlas {
+    instances: Vec<Instance>,
+    config: BvhConfig,
+    bvh: Bvh,
+}
+
+impl Tlas {
+    pub fn new(instances: Vec<Instance>, config: BvhConfig) -> Self {
+        assert!(!instances.is_empty(), "Tlas must have at least one instance!");
+        let bvh = Tlas::build(&instances, config);
+        Self {
+            instances,
+            config,
+            bvh,
+        }
+    }
+
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances
+    }
+
+    /// Move the instances to `transforms`, one per instance in order, e.g. between
+    /// animation frames, then rebuild the top level. The bottom-level structures are
+    /// reused as they are.
+    pub fn update_instances(&mut self, transforms: &[Matrix4<f64>]) {
+        assert_eq!(transforms.len(), self.instances.len(), "one transform per instance");
+        for (instance, transform) in self.instances.iter_mut().zip(transforms) {
+            instance.set_transform(*transform);
+        }
+        self.bvh = Tlas::build(&self.instances, self.config);
+    }
+
+    fn build(instances: &[Instance], config: BvhConfig) -> Bvh {
+        let placed = instances
+            .iter()
+            .map(|instance| {
+                let placed = Transformed::with_inverse(
+                    Arc::clone(&instance.geometry),
+                    instance.transform,
+                    instance.inverse,
+                );
+                Arc::new(placed) as Arc<dyn Hittable + Send + Sync>
+            })
+            .collect();
+        Bvh::new(&BvhNode::from_shared(placed, config), config)
+    }
+}
+
+impl Hittable for Tlas {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        self.bvh.hit(ray, interval)
+    }
+
+    fn get_aabb(&self) -> &AABB {
+        self.bvh.get_aabb()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::*;
+    use crate::material::Lambertian;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn moving_instances_rebuilds_only_the_top_level() {
+        let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let sphere = Box::new(Sphere::new(Point3::zeros(), 1.0, material)) as Box<dyn Hittable + Send + Sync>;
+        let blas: Arc<dyn Hittable + Send + Sync> = Arc::new(Bvh::from_list(vec![sphere], BvhConfig::default()));
+        let instances = [-3.0, 3.0]
+            .map(|x| Instance::new(Arc::clone(&blas), Matrix4::new_translation(&Vec3::new(x, 0.0, 0.0))));
+        let mut tlas = Tlas::new(instances.into(), BvhConfig::default());
+
+        let interval = Interval::new(0.001, f64::INFINITY);
+        let ray_at = |x: f64| Ray::new(Point3::new(x, 0.0, 10.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(tlas.hit(&ray_at(-3.0), &interval).is_some());
+        assert!(tlas.hit(&ray_at(0.0), &interval).is_none());
+
+        // Move the first copy to the middle and the second one back
+        let references = Arc::strong_count(&blas);
+        tlas.update_instances(&[
+            Matrix4::new_translation(&Vec3::zeros()),
+            Matrix4::new_translation(&Vec3::new(3.0, 0.0, -5.0)),
+        ]);
+        assert!(tlas.hit(&ray_at(-3.0), &interval).is_none());
+        let record = tlas.hit(&ray_at(0.0), &interval).expect("moved instance is hit");
+        assert!((record.t - 9.0).abs() < 1e-9);
+        let record = tlas.hit(&ray_at(3.0), &interval).expect("moved instance is hit");
+        assert!((record.t - 14.0).abs() < 1e-9);
+
+        // Both copies still share the original bottom-level BVH, and the old top level let
+        // go of it
+        assert!(tlas.instances().iter().all(|instance| Arc::ptr_eq(&instance.geometry, &blas)));
+        assert_eq!(Arc::strong_count(&blas), references);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::aabb::AABB;
+use crate::common::*;
+use crate::hittable::{HitRecord, Hittable, Interval};
+use crate::ray::Ray;
+use nalgebra::{Matrix3, Matrix4};
+
+/// An object placed in the world by an affine transform. Rays are moved into object
+/// space, so wrapping an `Arc` instances the same geometry many times cheaply.
+pub struct Transformed<H: Hittable> {
+    object: H,
+    matrix: Matrix4<f64>,
+    inverse: Matrix4<f64>,
+    // Inverse transpose of the linear part, maps normals to world space
+    normal_matrix: Matrix3<f64>,
+    aabb: AABB,
+}
+
+impl<H: Hittable> Transformed<H> {
+    pub fn new(object: H, matrix: Matrix4<f64>) -> Self {
+        let inverse = matrix
+            .try_inverse()
+            .expect("transform matrix must be invertible");
+        Self::with_inverse(object, matrix, inverse)
+    }
+
+    /// Like `new`, with the inverse of `matrix` already at hand.
+    pub fn with_inverse(object: H, matrix: Matrix4<f64>, inverse: Matrix4<f64>) -> Self {
+        let normal_matrix = inverse.fixed_view::<3, 3>(0, 0).transpose();
+        let aabb = transform_aabb(object.get_aabb(), &matrix);
+        Self {
+            object,
+            matrix,
+            inverse,
+            normal_matrix,
+            aabb,
+        }
+    }
+}
+
+impl<H: Hittable> Hittable for Transformed<H> {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        let origin = transform_point(&self.inverse, &ray.origin);
+        let direction = transform_vector(&self.inverse, &ray.direction);
+        // Ray::new normalizes the direction, so distances scale between the two spaces
+        let scale = direction.norm();
+        let object_ray = Ray::new(origin, direction, ray.time);
+        let object_interval = Interval::new(interval.start * scale, interval.end * scale);
+
+        let mut record = self.object.hit(&object_ray, &object_interval)?;
+        record.point = transform_point(&self.matrix, &record.point);
+        record.normal = (self.normal_matrix * record.normal).normalize();
+        record.t /= scale;
+        Some(record)
+    }
+
+    fn get_aabb(&self) -> &AABB {
+        &self.aabb
+    }
+}
+
+fn transform_point(matrix: &Matrix4<f64>, point: &Point3) -> Point3 {
+    (matrix * point.push(1.0)).xyz()
+}
+
+fn transform_vector(matrix: &Matrix4<f64>, vector: &Vec3) -> Vec3 {
+    (matrix * vector.push(0.0)).xyz()
+}
+
+/// World space box around the eight transformed corners of `aabb`.
+fn transform_aabb(aabb: &AABB, matrix: &Matrix4<f64>) -> AABB {
+    let (ix, iy, iz) = (aabb.axis_interval(0), aabb.axis_interval(1), aabb.axis_interval(2));
+    let mut result = AABB::new(Interval::EMPTY, Interval::EMPTY, Interval::EMPTY);
+    for x in [ix.start, ix.end] {
+        for y in [iy.start, iy.end] {
+            for z in [iz.start, iz.end] {
+                let corner = transform_point(matrix, &Point3::new(x, y, z));
+                result = AABB::merge(&result, &AABB::from_point(&corner, &corner));
+            }
+        }
+    }
+    result
+}
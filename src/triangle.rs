@@ -0,0 +1,135 @@
+use crate::aabb::AABB;
+use crate::common::*;
+use crate::hittable::{HitRecord, Hittable, Interval};
+use crate::material::Material;
+use crate::ray::Ray;
+use std::sync::Arc;
+
+pub struct Triangle {
+    pub vertices: [Point3; 3],
+    /// Per-vertex normals for smooth shading; `None` uses the face normal.
+    pub normals: Option<[Vec3; 3]>,
+    pub material: Arc<dyn Material>,
+    aabb: AABB,
+}
+
+impl Triangle {
+    pub fn new(vertices: [Point3; 3], material: Arc<dyn Material>) -> Self {
+        let aabb = triangle_aabb(&vertices);
+        Self {
+            vertices,
+            normals: None,
+            material,
+            aabb,
+        }
+    }
+
+    pub fn with_normals(
+        vertices: [Point3; 3],
+        normals: [Vec3; 3],
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let mut triangle = Self::new(vertices, material);
+        triangle.normals = Some(normals.map(|n| n.normalize()));
+        triangle
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        let (t, u, v) = intersect(&self.vertices, ray, interval)?;
+        Some(hit_record(
+            ray,
+            t,
+            (u, v),
+            (u, v),
+            &self.vertices,
+            self.normals.as_ref(),
+            &self.material,
+        ))
+    }
+
+    fn get_aabb(&self) -> &AABB {
+        &self.aabb
+    }
+}
+
+pub(crate) fn triangle_aabb(vertices: &[Point3; 3]) -> AABB {
+    let a = AABB::from_point(&vertices[0], &vertices[1]);
+    let b = AABB::from_point(&vertices[2], &vertices[2]);
+    AABB::merge(&a, &b).pad_to_minimums()
+}
+
+/// Möller–Trumbore intersection, returns `(t, u, v)` with `u`, `v` the barycentric
+/// weights of the second and third vertex.
+pub(crate) fn intersect(vertices: &[Point3; 3], ray: &Ray, interval: &Interval) -> Option<(f64, f64, f64)> {
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+    let p = ray.direction.cross(&edge2);
+    let det = edge1.dot(&p);
+    // Ray parallel to the triangle plane
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let s = ray.origin - vertices[0];
+    let u = s.dot(&p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(&edge1);
+    let v = ray.direction.dot(&q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(&q) * inv_det;
+    if t < interval.start || t > interval.end {
+        return None;
+    }
+    Some((t, u, v))
+}
+
+/// Build the hit record of an intersection found by [`intersect`]; `barycentric`
+/// weights the vertex normals, `uv` are the texture coordinates to report.
+pub(crate) fn hit_record(
+    ray: &Ray,
+    t: f64,
+    barycentric: (f64, f64),
+    uv: (f64, f64),
+    vertices: &[Point3; 3],
+    normals: Option<&[Vec3; 3]>,
+    material: &Arc<dyn Material>,
+) -> HitRecord {
+    let geometric_normal = (vertices[1] - vertices[0])
+        .cross(&(vertices[2] - vertices[0]))
+        .normalize();
+    // Front face is decided by the true surface, interpolated normals only shade
+    let front_face = ray.direction.dot(&geometric_normal) < 0.0;
+    let (u, v) = barycentric;
+    let outward_normal = match normals {
+        Some(n) => {
+            // Vertex normals may disagree with the winding order, follow the winding
+            let shading = ((1.0 - u - v) * n[0] + u * n[1] + v * n[2]).normalize();
+            if shading.dot(&geometric_normal) < 0.0 { -shading } else { shading }
+        }
+        None => geometric_normal,
+    };
+    let normal = if front_face {
+        outward_normal
+    } else {
+        -outward_normal
+    };
+
+    HitRecord {
+        point: ray.at(t),
+        normal,
+        t,
+        u: uv.0,
+        v: uv.1,
+        material: Arc::clone(material),
+        front_face,
+        on_light: false,
+    }
+}
@@ -0,0 +1,249 @@
+use crate::aabb::AABB;
+use crate::common::*;
+use crate::constant_medium::{boundary_segment, scatter_record};
+use crate::hittable::{HitRecord, Hittable, Interval};
+use crate::material::Material;
+use crate::perlin::Perlin;
+use crate::ray::{self, Ray};
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Spatially varying extinction coefficient of a medium.
+pub trait DensityField: Send + Sync {
+    fn density(&self, point: &Point3) -> f64;
+
+    /// Upper bound of `density` everywhere, the majorant for tracking.
+    fn max_density(&self) -> f64;
+}
+
+/// Density from a voxel grid stretched over a box, trilinearly interpolated between
+/// voxel centers.
+pub struct GridDensity {
+    resolution: [usize; 3],
+    min: Point3,
+    size: Vec3,
+    values: Vec<f64>,
+    max: f64,
+}
+
+impl GridDensity {
+    /// Read a raw voxel file: the resolution as three little-endian `u32` (x, y, z),
+    /// then one little-endian `f32` per voxel with x varying fastest, then y, then z.
+    /// Values are multiplied by `scale`, and the grid spans the box from `min` to `max`.
+    pub fn load<P: AsRef<Path>>(path: P, min: Point3, max: Point3, scale: f64) -> io::Result<Self> {
+        GridDensity::from_bytes(&std::fs::read(path)?, min, max, scale)
+    }
+
+    /// Like `load`, for the contents of a voxel file.
+    fn from_bytes(bytes: &[u8], min: Point3, max: Point3, scale: f64) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        if bytes.len() < 12 {
+            return Err(invalid("voxel file too short for its header".to_string()));
+        }
+
+        let word = |i: usize| <[u8; 4]>::try_from(&bytes[4 * i..4 * i + 4]).unwrap();
+        let resolution = [0, 1, 2].map(|i| u32::from_le_bytes(word(i)) as usize);
+        // A corrupt header must not wrap around and match the file size by accident
+        let count = resolution.iter().try_fold(1usize, |count, &n| count.checked_mul(n));
+        let Some(expected_len) = count.and_then(|count| count.checked_mul(4)?.checked_add(12)) else {
+            return Err(invalid(format!(
+                "voxel resolution {}x{}x{} is too large",
+                resolution[0], resolution[1], resolution[2]
+            )));
+        };
+        let count = (expected_len - 12) / 4;
+        if count == 0 || bytes.len() != expected_len {
+            return Err(invalid(format!(
+                "voxel file of {} bytes does not match resolution {}x{}x{}",
+                bytes.len(),
+                resolution[0],
+                resolution[1],
+                resolution[2]
+            )));
+        }
+
+        let values: Vec<f64> = (3..3 + count)
+            .map(|i| (f32::from_le_bytes(word(i)) as f64 * scale).max(0.0))
+            .collect();
+        let largest = values.iter().copied().fold(0.0, f64::max);
+        Ok(Self {
+            resolution,
+            min,
+            size: max - min,
+            values,
+            max: largest,
+        })
+    }
+
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        let [nx, ny, _] = self.resolution;
+        self.values[(z * ny + y) * nx + x]
+    }
+}
+
+impl DensityField for GridDensity {
+    fn density(&self, point: &Point3) -> f64 {
+        let mut index = [0; 3];
+        let mut fraction = [0.0; 3];
+        for axis in 0..3 {
+            let n = self.resolution[axis];
+            // Continuous grid coordinate with voxel centers on the integers
+            let g = (point[axis] - self.min[axis]) / self.size[axis] * n as f64 - 0.5;
+            let g = g.clamp(0.0, (n - 1) as f64);
+            index[axis] = (g.floor() as usize).min(n.saturating_sub(2));
+            fraction[axis] = g - index[axis] as f64;
+        }
+
+        let mut accumulated = 0.0;
+        for corner in 0..8 {
+            let offset = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let mut weight = 1.0;
+            let mut at = [0; 3];
+            for axis in 0..3 {
+                let f = fraction[axis];
+                weight *= if offset[axis] == 1 { f } else { 1.0 - f };
+                at[axis] = (index[axis] + offset[axis]).min(self.resolution[axis] - 1);
+            }
+            if weight > 0.0 {
+                accumulated += weight * self.voxel(at[0], at[1], at[2]);
+            }
+        }
+        accumulated
+    }
+
+    fn max_density(&self) -> f64 {
+        self.max
+    }
+}
+
+/// Billowing procedural density from Perlin fbm, between zero and `density`.
+pub struct NoiseDensity {
+    noise: Perlin,
+    scale: f64,
+    octaves: usize,
+    density: f64,
+}
+
+impl NoiseDensity {
+    pub fn new(seed: u64, scale: f64, octaves: usize, density: f64) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+            scale,
+            octaves,
+            density,
+        }
+    }
+}
+
+impl DensityField for NoiseDensity {
+    fn density(&self, point: &Point3) -> f64 {
+        // fbm rarely leaves [-0.25, 0.25], stretch it for clear gaps between the puffs
+        let n = self.noise.fbm(&(self.scale * point), self.octaves);
+        self.density * (0.5 + 2.0 * n).clamp(0.0, 1.0)
+    }
+
+    fn max_density(&self) -> f64 {
+        self.density
+    }
+}
+
+/// Medium of varying density inside a closed `boundary`. Collisions are placed by delta
+/// tracking against the field's majorant, so no ray marching step size needs tuning.
+pub struct HeterogeneousMedium {
+    boundary: Arc<dyn Hittable + Send + Sync>,
+    field: Arc<dyn DensityField>,
+    phase_function: Arc<dyn Material>,
+    /// Mixed into the per-ray seed; see `Ray::seed`.
+    salt: u64,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(
+        boundary: Arc<dyn Hittable + Send + Sync>,
+        field: Arc<dyn DensityField>,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            boundary,
+            field,
+            phase_function,
+            salt: ray::next_salt(),
+        }
+    }
+
+    /// Distance of the first real collision between `start` and `end`, if any.
+    fn delta_tracking(&self, ray: &Ray, start: f64, end: f64, rng: &mut SmallRng) -> Option<f64> {
+        let majorant = self.field.max_density();
+        if majorant <= 0.0 {
+            return None;
+        }
+
+        let mut t = start;
+        loop {
+            t -= random(rng).max(f64::MIN_POSITIVE).ln() / majorant;
+            if t >= end {
+                return None;
+            }
+            if random(rng) * majorant < self.field.density(&ray.at(t)) {
+                return Some(t);
+            }
+        }
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+        let (start, end) = boundary_segment(self.boundary.as_ref(), ray, interval)?;
+        let mut rng = SmallRng::seed_from_u64(ray.seed(self.salt));
+
+        let t = self.delta_tracking(ray, start, end, &mut rng)?;
+        Some(scatter_record(ray, t, &self.phase_function))
+    }
+
+    fn get_aabb(&self) -> &AABB {
+        self.boundary.get_aabb()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voxel_file(resolution: [u32; 3], values: &[f32]) -> Vec<u8> {
+        let header = resolution.iter().flat_map(|n| n.to_le_bytes());
+        header.chain(values.iter().flat_map(|v| v.to_le_bytes())).collect()
+    }
+
+    fn load(bytes: &[u8]) -> io::Result<GridDensity> {
+        GridDensity::from_bytes(bytes, Point3::zeros(), Point3::new(1.0, 1.0, 1.0), 2.0)
+    }
+
+    #[test]
+    fn loads_grid() {
+        let grid = load(&voxel_file([2, 1, 1], &[0.25, 1.5])).unwrap();
+        assert_eq!(grid.resolution, [2, 1, 1]);
+        assert_eq!(grid.values, vec![0.5, 3.0]);
+        assert_eq!(grid.max_density(), 3.0);
+    }
+
+    #[test]
+    fn rejects_truncated_file() {
+        let bytes = voxel_file([2, 2, 2], &[1.0; 8]);
+        for len in [0, 11, 12, bytes.len() - 1] {
+            let error = load(&bytes[..len]).err().expect("truncated file must not load");
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn rejects_overflowing_header() {
+        // The first header overflows the voxel count, the second only its size in bytes
+        for resolution in [[u32::MAX; 3], [u32::MAX, u32::MAX, 1]] {
+            let error = load(&voxel_file(resolution, &[1.0; 4])).err().expect("must not load");
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
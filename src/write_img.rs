@@ -1,17 +1,50 @@
 use image::codecs::jpeg::JpegEncoder;
-use image::ColorType;
+use image::codecs::png::PngEncoder;
+use image::{ColorType, ImageEncoder};
 use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::path::Path;
 
-pub fn write_jpg<P: AsRef<Path>>(
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OutputFormat {
+    Jpg,
+    Png,
+    Ppm,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "jpg" | "jpeg" => Some(OutputFormat::Jpg),
+            "png" => Some(OutputFormat::Png),
+            "ppm" => Some(OutputFormat::Ppm),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from the file extension of `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        Self::from_name(extension)
+    }
+}
+
+pub fn write_image<P: AsRef<Path>>(
     path: P,
+    format: OutputFormat,
     width: usize,
     height: usize,
     buffer: &[u8],
-    quality: u8,
 ) -> std::io::Result<()> {
-    // Validate buffer length = width * height * 3 (RGB)
+    match format {
+        OutputFormat::Jpg => write_jpg(path, width, height, buffer, 100),
+        OutputFormat::Png => write_png(path, width, height, buffer),
+        OutputFormat::Ppm => write_ppm(path, width, height, buffer),
+    }
+}
+
+/// Validate buffer length = width * height * 3 (RGB) and convert the dimensions to u32.
+fn check_dimensions(width: usize, height: usize, buffer: &[u8]) -> std::io::Result<(u32, u32)> {
     let expected = width
         .checked_mul(height)
         .and_then(|px| px.checked_mul(3))
@@ -26,7 +59,7 @@ pub fn write_jpg<P: AsRef<Path>>(
         ));
     }
 
-    // JPEG encoder expects u32 dimensions
+    // Encoders expect u32 dimensions
     let w = u32::try_from(width).map_err(|_| {
         std::io::Error::new(
             std::io::ErrorKind::InvalidInput,
@@ -39,6 +72,17 @@ pub fn write_jpg<P: AsRef<Path>>(
             "height does not fit into u32",
         )
     })?;
+    Ok((w, h))
+}
+
+pub fn write_jpg<P: AsRef<Path>>(
+    path: P,
+    width: usize,
+    height: usize,
+    buffer: &[u8],
+    quality: u8,
+) -> std::io::Result<()> {
+    let (w, h) = check_dimensions(width, height, buffer)?;
 
     // Create file and encoder
     let file = File::create(path)?;
@@ -48,7 +92,43 @@ pub fn write_jpg<P: AsRef<Path>>(
     // Encode as RGB8
     encoder
         .encode(buffer, w, h, ColorType::Rgb8.into())
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_png<P: AsRef<Path>>(
+    path: P,
+    width: usize,
+    height: usize,
+    buffer: &[u8],
+) -> std::io::Result<()> {
+    let (w, h) = check_dimensions(width, height, buffer)?;
+
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    PngEncoder::new(&mut writer)
+        .write_image(buffer, w, h, ColorType::Rgb8.into())
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_ppm<P: AsRef<Path>>(
+    path: P,
+    width: usize,
+    height: usize,
+    buffer: &[u8],
+) -> std::io::Result<()> {
+    check_dimensions(width, height, buffer)?;
+
+    // Binary PPM: header followed by raw RGB bytes
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    write!(writer, "P6\n{} {}\n255\n", width, height)?;
+    writer.write_all(buffer)?;
 
     writer.flush()?;
     Ok(())